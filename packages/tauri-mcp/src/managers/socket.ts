@@ -27,6 +27,284 @@ export interface JsonRpcResponse {
   };
 }
 
+export interface WebviewInfo {
+  label: string;
+  window: string;
+  url: string | null;
+  bounds: { x: number; y: number; width: number; height: number } | null;
+}
+
+export interface WindowState {
+  label: string;
+  title: string;
+  focused: boolean;
+  visible: boolean;
+  minimized: boolean;
+  maximized: boolean;
+  fullscreen: boolean;
+  scale_factor: number;
+  position: { x: number; y: number } | null;
+  size: { width: number; height: number } | null;
+}
+
+export type WindowAction =
+  | 'resize' | 'move' | 'minimize' | 'maximize' | 'unmaximize'
+  | 'fullscreen' | 'exit_fullscreen' | 'show' | 'hide' | 'close';
+
+export interface WindowEventRecord {
+  id: number;
+  timestamp: number;
+  window: string;
+  event: string;
+  data?: Record<string, unknown>;
+}
+
+export interface ScreenshotOptions {
+  window?: string;
+  webview?: string;
+  full_page?: boolean;
+  scroll_container?: string;
+  format?: 'png' | 'jpeg' | 'webp';
+  quality?: number;
+  max_width?: number;
+  max_height?: number;
+  scale?: number;
+  path?: string;
+  mask?: Array<number | string>;
+  mask_color?: string;
+  disable_animations?: boolean;
+  annotate?: boolean;
+}
+
+export interface CompareScreenshotOptions extends Omit<ScreenshotOptions, 'format' | 'quality' | 'path' | 'annotate'> {
+  name: string;
+  update?: boolean;
+  threshold?: number;
+  anti_aliasing?: boolean;
+  max_diff_percentage?: number;
+}
+
+export interface CompareScreenshotResult {
+  passed: boolean;
+  baseline: string;
+  width: number;
+  height: number;
+  created?: boolean;
+  updated?: boolean;
+  diff_pixels?: number;
+  anti_aliased_pixels?: number;
+  diff_percentage?: number;
+  baseline_width?: number;
+  baseline_height?: number;
+  /** Path of the diff image (`<name>.diff.png` next to the baseline), present when pixels differ */
+  diff?: string;
+}
+
+export interface RecordingOptions {
+  fps?: number;
+  max_duration_ms?: number;
+  max_width?: number;
+  max_height?: number;
+  window?: string;
+}
+
+export interface RecordingResult {
+  path: string;
+  format: 'apng' | 'png';
+  window: string;
+  frames: number;
+  duration_ms: number;
+  fps: number;
+  width: number;
+  height: number;
+  truncated: boolean;
+  failed_frames: number;
+}
+
+export interface WaitForOptions {
+  selector?: string;
+  ref?: number;
+  state?: 'attached' | 'detached' | 'visible' | 'hidden' | 'enabled';
+  text?: string;
+  url?: string;
+  predicate?: string;
+  timeout_ms?: number;
+  poll_ms?: number;
+  window?: string;
+  webview?: string;
+}
+
+export interface SnapshotOptions {
+  format?: 'text' | 'json';
+  // Snapshot ref or locator of the subtree to snapshot
+  root?: number | string;
+  max_depth?: number;
+  max_nodes?: number;
+  cursor?: string;
+  interactive_only?: boolean;
+  include_bounds?: boolean;
+  since?: string;
+  window?: string;
+  webview?: string;
+}
+
+export interface IdleOptions {
+  idle_ms?: number;
+  timeout_ms?: number;
+  window?: string;
+  webview?: string;
+}
+
+export interface VisualStableOptions {
+  ref?: number;
+  selector?: string;
+  threshold?: number;
+  max_diff_percentage?: number;
+  stable_frames?: number;
+  interval_ms?: number;
+  timeout_ms?: number;
+  window?: string;
+  webview?: string;
+}
+
+// Maps viewport CSS pixels to screenshot pixels: image = offset + css * scale
+export interface ImageTransform {
+  scale: number;
+  offset_x: number;
+  offset_y: number;
+  device_pixel_ratio: number;
+  resize_ratio: number;
+  webview_x?: number;
+  webview_y?: number;
+}
+
+// A snapshot ref drawn on an annotated screenshot, in image pixels
+export interface Annotation {
+  ref: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+// Element found at a screenshot point by click_at/element_at
+export interface PointResult {
+  success: boolean;
+  error?: string;
+  // Viewport point in CSS pixels
+  x?: number;
+  y?: number;
+  element?: {
+    tag: string;
+    id?: string;
+    role?: string;
+    text?: string;
+    ref?: number;
+    rect: { x: number; y: number; width: number; height: number };
+  };
+}
+
+// Elements matching a locator, in document order
+export interface LocateResult {
+  success: boolean;
+  error?: string;
+  count?: number;
+  elements?: Array<{
+    tag: string;
+    role?: string;
+    name?: string;
+    ref?: number;
+    visible: boolean;
+    rect: { x: number; y: number; width: number; height: number };
+  }>;
+}
+
+export interface ElementActionOptions {
+  ref?: number;
+  selector?: string;
+  force?: boolean;
+  timeout_ms?: number;
+  window?: string;
+  webview?: string;
+}
+
+export interface PressOptions extends ElementActionOptions {
+  key: string;
+}
+
+export interface TypeTextOptions extends ElementActionOptions {
+  text: string;
+  delay_ms?: number;
+}
+
+export type SelectOptionValue = string | { value?: string; label?: string; index?: number };
+
+export interface SelectOptionOptions extends ElementActionOptions {
+  values: SelectOptionValue | SelectOptionValue[];
+}
+
+export interface SetCheckedOptions extends ElementActionOptions {
+  checked?: boolean;
+}
+
+export interface SetInputFilesOptions extends ElementActionOptions {
+  paths: string[];
+}
+
+export interface MouseOptions {
+  x: number;
+  y: number;
+  button?: 'left' | 'middle' | 'right';
+  transform?: ImageTransform;
+  window?: string;
+  webview?: string;
+}
+
+export interface DragOptions {
+  from_ref?: number;
+  from_selector?: string;
+  to_ref?: number;
+  to_selector?: string;
+  steps?: number;
+  force?: boolean;
+  timeout_ms?: number;
+  window?: string;
+  webview?: string;
+}
+
+export interface DragResult {
+  success: boolean;
+  error?: string;
+  // html5: dragstart/dragover/drop with a DataTransfer; pointer: pointer and mouse moves
+  mode: 'html5' | 'pointer';
+  dropped?: boolean;
+  from: { x: number; y: number };
+  to: { x: number; y: number };
+}
+
+/** Screenshot result: inline base64 `data`, or the `path` it was written to */
+export interface ImageResult {
+  data?: string;
+  path?: string;
+  mimeType: string;
+  width: number;
+  height: number;
+  bytes?: number;
+  transform?: ImageTransform;
+  annotations?: Annotation[];
+}
+
+interface RawImageResult {
+  data?: string;
+  path?: string;
+  mimeType?: string;
+  width: number;
+  height: number;
+  bytes?: number;
+  transform?: ImageTransform;
+  annotations?: Annotation[];
+}
+
 export type SocketPathProvider = () => string;
 
 export class SocketManager {
@@ -34,6 +312,8 @@ export class SocketManager {
   private socketPathProvider: SocketPathProvider | null = null;
   private static readonly MAX_RETRIES = 3;
   private static readonly RETRY_DELAY_MS = 500;
+  private static readonly COMMAND_TIMEOUT_MS = 30000;
+  private static readonly FULL_PAGE_TIMEOUT_MS = 120000;
 
   constructor(projectRoot?: string) {
     this.projectRoot = projectRoot ?? process.env.TAURI_PROJECT_ROOT ?? process.cwd();
@@ -128,11 +408,35 @@ export class SocketManager {
     return new Promise((resolve) => setTimeout(resolve, ms));
   }
 
+  /**
+   * How long to wait for a command's response
+   *
+   * Commands that wait in the app (timeout_ms), type with a delay or drag in
+   * steps get that time on top of the default, so the app's own timeout error
+   * arrives before the client gives up.
+   */
+  private static commandTimeoutMs(method: string, params: Record<string, unknown>): number {
+    const positive = (value: unknown) => (typeof value === 'number' && value > 0 ? value : 0);
+    let waits = positive(params.timeout_ms);
+    if (method === 'drag') {
+      // Both ends wait for actionability, then the pointer moves about a frame per step
+      waits = waits * 2 + Math.min(Math.max(positive(params.steps), 1), 200) * 16;
+    }
+    if (method === 'type_text' && typeof params.text === 'string') {
+      waits += positive(params.delay_ms) * [...params.text].length;
+    }
+    if (method === 'screenshot' && params.full_page) {
+      waits += SocketManager.FULL_PAGE_TIMEOUT_MS;
+    }
+    return SocketManager.COMMAND_TIMEOUT_MS + waits;
+  }
+
   /**
    * Send a single command without retry
    */
   private async sendCommandOnce(method: string, params: Record<string, unknown> = {}): Promise<unknown> {
     const socketPath = this.getSocketPath();
+    const timeoutMs = SocketManager.commandTimeoutMs(method, params);
 
     return new Promise((resolve, reject) => {
       const client = net.createConnection(socketPath, () => {
@@ -183,18 +487,17 @@ export class SocketManager {
         }
       });
 
-      // Timeout after 30 seconds
       setTimeout(() => {
         client.destroy();
-        reject(new Error('Command timed out after 30 seconds'));
-      }, 30000);
+        reject(new Error(`Command timed out after ${Math.round(timeoutMs / 1000)} seconds`));
+      }, timeoutMs);
     });
   }
 
   // Multi-window support methods
 
-  async listWindows(): Promise<{ windows: Array<{ label: string; title: string; focused: boolean; visible: boolean; size: { width: number; height: number } | null }> }> {
-    const result = await this.sendCommand('list_windows') as { windows: Array<{ label: string; title: string; focused: boolean; visible: boolean; size: { width: number; height: number } | null }> };
+  async listWindows(): Promise<{ windows: Array<{ label: string; title: string; focused: boolean; visible: boolean; size: { width: number; height: number } | null; webviews: WebviewInfo[] }> }> {
+    const result = await this.sendCommand('list_windows') as { windows: Array<{ label: string; title: string; focused: boolean; visible: boolean; size: { width: number; height: number } | null; webviews: WebviewInfo[] }> };
     return result;
   }
 
@@ -203,16 +506,86 @@ export class SocketManager {
     return `Focused window: ${result.focused}`;
   }
 
-  async snapshot(options?: { window?: string }): Promise<string> {
+  async createWindow(options: { label: string; url?: string; title?: string; width?: number; height?: number }): Promise<WindowState> {
+    const result = await this.sendCommand('create_window', options) as WindowState;
+    return result;
+  }
+
+  async manageWindow(
+    action: WindowAction,
+    options: { window?: string; width?: number; height?: number; x?: number; y?: number } = {}
+  ): Promise<WindowState | { closed: string }> {
     const params: Record<string, unknown> = {};
+    if (options.window) params.window = options.window;
+
+    switch (action) {
+      case 'resize':
+        return await this.sendCommand('set_window_size', { ...params, width: options.width, height: options.height }) as WindowState;
+      case 'move':
+        return await this.sendCommand('set_window_position', { ...params, x: options.x, y: options.y }) as WindowState;
+      case 'fullscreen':
+      case 'exit_fullscreen':
+        return await this.sendCommand('set_fullscreen', { ...params, fullscreen: action === 'fullscreen' }) as WindowState;
+      case 'close':
+        return await this.sendCommand('close_window', params) as { closed: string };
+      default:
+        return await this.sendCommand(`${action}_window`, params) as WindowState;
+    }
+  }
+
+  async waitForWindow(options: { label?: string; title?: string; url?: string; since?: number; timeout_ms?: number }): Promise<WindowState & { webviews: WebviewInfo[]; elapsed_ms: number }> {
+    const result = await this.sendCommand('wait_for_window', options) as WindowState & { webviews: WebviewInfo[]; elapsed_ms: number };
+    return result;
+  }
+
+  async interceptPopups(options: { mode: 'report' | 'redirect' | 'off'; window?: string; webview?: string }): Promise<string> {
+    const result = await this.sendCommand('intercept_popups', options) as { success: boolean; mode?: string; error?: string };
+    if (!result.success) {
+      throw new Error(result.error || 'Intercept popups failed');
+    }
+    return `Popup mode: ${result.mode}`;
+  }
+
+  async getPopups(options?: { clear?: boolean; window?: string; webview?: string }): Promise<{
+    mode: string;
+    popups: Array<{ url: string; target: string; features: string; source: string; action: string; timestamp: number }>;
+  }> {
+    const params: Record<string, unknown> = { clear: options?.clear ?? false };
     if (options?.window) params.window = options.window;
+    if (options?.webview) params.webview = options.webview;
+
+    const result = await this.sendCommand('get_popups', params);
+    return result as {
+      mode: string;
+      popups: Array<{ url: string; target: string; features: string; source: string; action: string; timestamp: number }>;
+    };
+  }
 
-    const result = await this.sendCommand('snapshot', params) as { window: string; snapshot: string; title: string; url: string };
+  async getWindowEvents(options?: { since?: number; window?: string; clear?: boolean }): Promise<{ events: WindowEventRecord[]; cursor: number; dropped: boolean }> {
+    const params: Record<string, unknown> = { since: options?.since ?? 0, clear: options?.clear ?? false };
+    if (options?.window) params.window = options.window;
+
+    const result = await this.sendCommand('get_window_events', params) as { events: WindowEventRecord[]; cursor: number; dropped: boolean };
+    return result;
+  }
+
+  async snapshot(options: SnapshotOptions = {}): Promise<string> {
+    const result = await this.sendCommand('snapshot', options) as {
+      window: string; snapshot?: string; title: string; url: string; snapshotId: string; since?: string; note?: string;
+      tree?: unknown[]; diff?: unknown; truncated?: { remaining: number; cursor?: string };
+    };
+    if (options.format === 'json') {
+      const { window, title, url, snapshotId, since, note, tree, diff, truncated } = result;
+      // Compact: indentation would add more tokens than the tree itself
+      return JSON.stringify({ window, title, url, snapshotId, since, note, tree, diff, truncated });
+    }
     // Format as readable output with window label
-    return `# [${result.window}] ${result.title}\nURL: ${result.url}\n\n${result.snapshot}`;
+    const id = result.since ? `${result.snapshotId} (changes since ${result.since})` : result.snapshotId;
+    const note = result.note ? `Note: ${result.note}\n` : '';
+    return `# [${result.window}] ${result.title}\nURL: ${result.url}\nSnapshot: ${id}\n${note}\n${result.snapshot}`;
   }
 
-  async click(options: { ref?: number; selector?: string; window?: string }): Promise<string> {
+  async click(options: { ref?: number; selector?: string; force?: boolean; timeout_ms?: number; window?: string; webview?: string }): Promise<string> {
     const result = await this.sendCommand('click', options) as { success: boolean; error?: string };
     if (!result.success) {
       throw new Error(result.error || 'Click failed');
@@ -222,7 +595,58 @@ export class SocketManager {
     return `Clicked ${target}${windowInfo}`;
   }
 
-  async fill(options: { ref?: number; selector?: string; value: string; window?: string }): Promise<string> {
+  async clickAt(options: { x: number; y: number; transform?: ImageTransform; window?: string; webview?: string }): Promise<PointResult> {
+    const result = await this.sendCommand('click_at', options) as PointResult;
+    if (!result.success) {
+      throw new Error(result.error || 'Click failed');
+    }
+    return result;
+  }
+
+  async pointerAction(action: 'hover' | 'dblclick' | 'right_click', options: ElementActionOptions): Promise<string> {
+    const result = await this.sendCommand(action, options) as { success: boolean; error?: string };
+    if (!result.success) {
+      throw new Error(result.error || `${action} failed`);
+    }
+    const verb = { hover: 'Hovered', dblclick: 'Double-clicked', right_click: 'Right-clicked' }[action];
+    const target = options.ref ? `ref=${options.ref}` : options.selector;
+    const windowInfo = options.window ? ` in window '${options.window}'` : '';
+    return `${verb} ${target}${windowInfo}`;
+  }
+
+  async mouse(action: 'mouse_down' | 'mouse_up' | 'mouse_move', options: MouseOptions): Promise<PointResult & { buttons: number }> {
+    const result = await this.sendCommand(action, options) as PointResult & { buttons: number };
+    if (!result.success) {
+      throw new Error(result.error || `${action} failed`);
+    }
+    return result;
+  }
+
+  async drag(options: DragOptions): Promise<DragResult> {
+    const result = await this.sendCommand('drag', options) as DragResult;
+    if (!result.success) {
+      throw new Error(result.error || 'Drag failed');
+    }
+    return result;
+  }
+
+  async elementAt(options: { x: number; y: number; transform?: ImageTransform; window?: string; webview?: string }): Promise<PointResult> {
+    const result = await this.sendCommand('element_at', options) as PointResult;
+    if (!result.success) {
+      throw new Error(result.error || 'No element found');
+    }
+    return result;
+  }
+
+  async locate(options: { selector: string; limit?: number; window?: string; webview?: string }): Promise<LocateResult> {
+    const result = await this.sendCommand('locate', options) as LocateResult;
+    if (!result.success) {
+      throw new Error(result.error || 'Locate failed');
+    }
+    return result;
+  }
+
+  async fill(options: { ref?: number; selector?: string; value: string; force?: boolean; timeout_ms?: number; window?: string; webview?: string }): Promise<string> {
     const result = await this.sendCommand('fill', options) as { success: boolean; error?: string };
     if (!result.success) {
       throw new Error(result.error || 'Fill failed');
@@ -232,9 +656,10 @@ export class SocketManager {
     return `Filled ${target} with "${options.value}"${windowInfo}`;
   }
 
-  async pressKey(key: string, windowLabel?: string): Promise<string> {
+  async pressKey(key: string, windowLabel?: string, webviewLabel?: string): Promise<string> {
     const params: Record<string, unknown> = { key };
     if (windowLabel) params.window = windowLabel;
+    if (webviewLabel) params.webview = webviewLabel;
 
     const result = await this.sendCommand('press_key', params) as { success: boolean; error?: string };
     if (!result.success) {
@@ -244,18 +669,68 @@ export class SocketManager {
     return `Pressed key: ${key}${windowInfo}`;
   }
 
-  async evaluateScript(script: string, windowLabel?: string): Promise<unknown> {
+  async selectOption(options: SelectOptionOptions): Promise<string[]> {
+    const result = await this.sendCommand('select_option', options) as { success: boolean; error?: string; values?: string[] };
+    if (!result.success) {
+      throw new Error(result.error || 'Select option failed');
+    }
+    return result.values || [];
+  }
+
+  async setChecked(options: SetCheckedOptions): Promise<{ checked: boolean; changed: boolean }> {
+    const result = await this.sendCommand('set_checked', options) as { success: boolean; error?: string; checked: boolean; changed: boolean };
+    if (!result.success) {
+      throw new Error(result.error || 'Set checked failed');
+    }
+    return { checked: result.checked, changed: result.changed };
+  }
+
+  async setInputFiles(options: SetInputFilesOptions): Promise<Array<{ name: string; size: number; type: string }>> {
+    const result = await this.sendCommand('set_input_files', options) as { success: boolean; error?: string; files?: Array<{ name: string; size: number; type: string }> };
+    if (!result.success) {
+      throw new Error(result.error || 'Set input files failed');
+    }
+    return result.files || [];
+  }
+
+  async press(options: PressOptions): Promise<string> {
+    const result = await this.sendCommand('press', options) as { success: boolean; error?: string };
+    if (!result.success) {
+      throw new Error(result.error || 'Press failed');
+    }
+    const windowInfo = options.window ? ` in window '${options.window}'` : '';
+    return `Pressed ${options.key}${windowInfo}`;
+  }
+
+  async typeText(options: TypeTextOptions): Promise<string> {
+    const result = await this.sendCommand('type_text', options) as { success: boolean; error?: string; typed?: number };
+    if (!result.success) {
+      throw new Error(result.error || 'Typing failed');
+    }
+    const windowInfo = options.window ? ` in window '${options.window}'` : '';
+    return `Typed ${result.typed} characters${windowInfo}`;
+  }
+
+  async evaluateScript(script: string, windowLabel?: string, webviewLabel?: string): Promise<unknown> {
     const params: Record<string, unknown> = { script };
     if (windowLabel) params.window = windowLabel;
+    if (webviewLabel) params.webview = webviewLabel;
 
     const result = await this.sendCommand('evaluate_script', params);
     return result;
   }
 
-  async screenshot(options?: { window?: string }): Promise<{ data: string; mimeType: string; width: number; height: number }> {
+  async screenshot(options?: ScreenshotOptions): Promise<ImageResult> {
     // On macOS, use screencapture command which doesn't require Screen Recording permission
-    // when capturing by window ID (the app captures its own window)
-    if (os.platform() === 'darwin') {
+    // when capturing by window ID (the app captures its own window).
+    // Full-page captures, output options and masks need the Rust side to prepare, scroll,
+    // stitch, paint and encode.
+    const savesPng = !options?.path || path.extname(options.path).toLowerCase() === '.png';
+    const needsNative = options?.full_page || options?.format || options?.quality
+      || options?.max_width || options?.max_height || options?.scale || !savesPng
+      || options?.mask?.length || options?.mask_color || options?.disable_animations
+      || options?.annotate;
+    if (os.platform() === 'darwin' && !needsNative) {
       return this.screenshotMacOS(options);
     }
 
@@ -263,16 +738,22 @@ export class SocketManager {
     return this.screenshotNative(options);
   }
 
-  private async screenshotMacOS(options?: { window?: string }): Promise<{ data: string; mimeType: string; width: number; height: number }> {
+  private async screenshotMacOS(options?: ScreenshotOptions): Promise<ImageResult> {
     // Get window ID from Tauri app
     const params: Record<string, unknown> = {};
     if (options?.window) params.window = options.window;
+    if (options?.webview) params.webview = options.webview;
 
     const windowInfo = await this.sendCommand('get_window_id', params) as { window_id: number; pid: number };
     const windowId = windowInfo.window_id;
 
-    // Create temp file for screenshot
-    const tmpFile = path.join(os.tmpdir(), `tauri-mcp-screenshot-${Date.now()}.png`);
+    // Write to the requested path, or to a temp file that is read back and removed
+    const outputFile = options?.path
+      ? path.resolve(options.path)
+      : path.join(os.tmpdir(), `tauri-mcp-screenshot-${Date.now()}.png`);
+    if (options?.path) {
+      fs.mkdirSync(path.dirname(outputFile), { recursive: true });
+    }
 
     try {
       // Use screencapture command with window ID
@@ -283,11 +764,11 @@ export class SocketManager {
         `-l${windowId}`,
         '-x',
         '-o',
-        tmpFile
+        outputFile
       ]);
 
       // Read the file and convert to base64
-      const imageBuffer = fs.readFileSync(tmpFile);
+      const imageBuffer = fs.readFileSync(outputFile);
       const base64Data = imageBuffer.toString('base64');
 
       // Get image dimensions (basic PNG header parsing)
@@ -298,46 +779,132 @@ export class SocketManager {
         width = imageBuffer.readUInt32BE(16);
         height = imageBuffer.readUInt32BE(20);
       }
-
+      // Let the app relate this capture to page coordinates (and remember it for click_at)
+      const transform = await this.sendCommand('screenshot_transform', { ...params, width, height }) as ImageTransform;
+
+      if (options?.path) {
+        return {
+          path: outputFile,
+          mimeType: 'image/png',
+          width,
+          height,
+          bytes: imageBuffer.length,
+          transform,
+        };
+      }
       return {
         data: base64Data,
         mimeType: 'image/png',
         width,
-        height
+        height,
+        transform,
       };
     } finally {
       // Clean up temp file
-      try {
-        fs.unlinkSync(tmpFile);
-      } catch {
-        // Ignore cleanup errors
+      if (!options?.path) {
+        try {
+          fs.unlinkSync(outputFile);
+        } catch {
+          // Ignore cleanup errors
+        }
       }
     }
   }
 
-  private async screenshotNative(options?: { window?: string }): Promise<{ data: string; mimeType: string; width: number; height: number }> {
+  private async screenshotNative(options?: ScreenshotOptions): Promise<ImageResult> {
+    const params: Record<string, unknown> = { ...options };
+    // Relative paths are resolved here, not against the app's working directory
+    if (options?.path) params.path = path.resolve(options.path);
+
+    const result = await this.sendCommand('screenshot', params) as RawImageResult;
+    return this.parseImageResult(result);
+  }
+
+  async compareScreenshot(options: CompareScreenshotOptions): Promise<CompareScreenshotResult> {
+    const result = await this.sendCommand('compare_screenshot', { ...options });
+    return result as CompareScreenshotResult;
+  }
+
+  async waitFor(options: WaitForOptions): Promise<{ met: boolean; elapsed_ms: number; state: unknown }> {
+    const result = await this.sendCommand('wait_for', { ...options });
+    return result as { met: boolean; elapsed_ms: number; state: unknown };
+  }
+
+  async waitForIdle(options: IdleOptions): Promise<{ met: boolean; elapsed_ms: number; state: unknown }> {
+    const result = await this.sendCommand('wait_for_idle', { ...options });
+    return result as { met: boolean; elapsed_ms: number; state: unknown };
+  }
+
+  async waitForVisualStable(options: VisualStableOptions): Promise<{ stable: boolean; elapsed_ms: number; frames: number; diff_percentage: number }> {
+    const result = await this.sendCommand('wait_for_visual_stable', { ...options });
+    return result as { stable: boolean; elapsed_ms: number; frames: number; diff_percentage: number };
+  }
+
+  async startRecording(options?: RecordingOptions): Promise<{ recording: boolean; window: string; fps: number }> {
+    const result = await this.sendCommand('start_recording', { ...options });
+    return result as { recording: boolean; window: string; fps: number };
+  }
+
+  async stopRecording(options?: { format?: 'apng' | 'png'; path?: string }): Promise<RecordingResult> {
     const params: Record<string, unknown> = {};
-    if (options?.window) params.window = options.window;
+    if (options?.format) params.format = options.format;
+    // Relative paths are resolved here, not against the app's working directory
+    if (options?.path) params.path = path.resolve(options.path);
 
-    const result = await this.sendCommand('screenshot', params) as { data: string; width: number; height: number };
+    const result = await this.sendCommand('stop_recording', params);
+    return result as RecordingResult;
+  }
+
+  async screenshotElement(options: { ref?: number; selector?: string } & ScreenshotOptions): Promise<ImageResult> {
+    const params: Record<string, unknown> = { ...options };
+    if (options.path) params.path = path.resolve(options.path);
+
+    const result = await this.sendCommand('screenshot_element', params) as RawImageResult;
+    return this.parseImageResult(result);
+  }
+
+  private parseImageResult(result: RawImageResult): ImageResult {
+    // Saved to disk: only the path and metadata are returned
+    if (result.path) {
+      return {
+        path: result.path,
+        mimeType: result.mimeType || 'image/png',
+        width: result.width,
+        height: result.height,
+        bytes: result.bytes,
+        transform: result.transform,
+        annotations: result.annotations,
+      };
+    }
     // data is a Data URL like "data:image/jpeg;base64,..."
     // Extract the base64 part and mime type
-    const match = result.data.match(/^data:([^;]+);base64,(.+)$/);
+    const data = result.data || '';
+    const match = data.match(/^data:([^;]+);base64,(.+)$/);
     if (match) {
       return {
         data: match[2],
         mimeType: match[1],
         width: result.width,
         height: result.height,
+        transform: result.transform,
+        annotations: result.annotations,
       };
     }
     // Fallback: assume it's already raw base64
-    return { ...result, mimeType: 'image/png' };
+    return {
+      data,
+      mimeType: 'image/png',
+      width: result.width,
+      height: result.height,
+      transform: result.transform,
+      annotations: result.annotations,
+    };
   }
 
-  async navigate(url: string, windowLabel?: string): Promise<string> {
+  async navigate(url: string, windowLabel?: string, webviewLabel?: string): Promise<string> {
     const params: Record<string, unknown> = { url };
     if (windowLabel) params.window = windowLabel;
+    if (webviewLabel) params.webview = webviewLabel;
 
     const result = await this.sendCommand('navigate', params) as { success: boolean; error?: string };
     if (!result.success) {
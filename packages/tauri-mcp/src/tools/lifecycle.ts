@@ -1,6 +1,74 @@
 import { z } from 'zod';
 import { TauriManager } from '../managers/tauri.js';
-import { SocketManager } from '../managers/socket.js';
+import {
+  CompareScreenshotOptions, DragOptions, ElementActionOptions, ImageResult, MouseOptions, PressOptions, SelectOptionOptions,
+  SetCheckedOptions, SetInputFilesOptions, SnapshotOptions, TypeTextOptions, ImageTransform, RecordingOptions, ScreenshotOptions, SocketManager,
+  IdleOptions, VisualStableOptions, WaitForOptions, WindowAction,
+} from '../managers/socket.js';
+
+// Output options shared by the screenshot tools
+const imageOutputSchema = {
+  format: z.enum(['png', 'jpeg', 'webp']).optional().describe('Image format (default: png, or from path extension)'),
+  quality: z.number().min(1).max(100).optional().describe('JPEG quality 1-100 (WebP is always lossless)'),
+  max_width: z.number().optional().describe('Maximum width in pixels (default: 1920)'),
+  max_height: z.number().optional().describe('Maximum height in pixels (default: 1080)'),
+  scale: z.number().positive().optional().describe('Scale factor applied before max size'),
+  path: z.string().optional().describe('Write the image to this file and return only its path'),
+};
+
+// Options making captures deterministic, shared by screenshot and compare tools
+const captureSetupSchema = {
+  mask: z.array(z.union([z.number(), z.string()])).optional().describe('Refs or locators to paint (every match) over (e.g. clocks, avatars)'),
+  mask_color: z.string().optional().describe('Mask color as #RRGGBB (default: #FF00FF)'),
+  disable_animations: z.boolean().optional().describe('Pause CSS transitions and animations while capturing (default: false)'),
+};
+
+// Element locator accepted wherever a selector is: CSS by default, or an engine
+// prefix, with parts chained by >> each resolved inside the previous matches
+const locatorSchema = z.string().optional().describe(
+  'Locator: CSS, role=button[name="Save"], text=Save, label=Email, testid=submit, xpath=//button, nth=<index>, chained with >>. Must match one element',
+);
+
+// Coordinate transform returned with every screenshot
+const imageTransformSchema = z.object({
+  scale: z.number(),
+  offset_x: z.number(),
+  offset_y: z.number(),
+  device_pixel_ratio: z.number(),
+  resize_ratio: z.number(),
+  webview_x: z.number().optional(),
+  webview_y: z.number().optional(),
+});
+
+// Return an image inline, or its path and metadata when it was written to disk
+function imageContent(result: ImageResult) {
+  if (result.path) {
+    return {
+      content: [
+        {
+          type: 'text' as const,
+          text: JSON.stringify(result, null, 2),
+        },
+      ],
+    };
+  }
+  const content: Array<{ type: 'image'; data: string; mimeType: string } | { type: 'text'; text: string }> = [
+    {
+      type: 'image' as const,
+      data: result.data || '',
+      mimeType: result.mimeType,
+    },
+  ];
+  // The transform relates image pixels to the page; annotated screenshots
+  // also list each drawn ref with its box in image pixels
+  if (result.transform || result.annotations) {
+    content.push({
+      type: 'text' as const,
+      text: JSON.stringify({ transform: result.transform, annotations: result.annotations }, null, 2),
+    });
+  }
+  return { content };
+}
 
 // Tool schemas (descriptions kept minimal for context efficiency)
 export const toolSchemas = {
@@ -26,7 +94,7 @@ export const toolSchemas = {
   },
   list_windows: {
     name: 'list_windows',
-    description: 'List all open windows with their labels, titles, and focus state',
+    description: 'List all open windows with their labels, titles, focus state, and webviews',
     inputSchema: z.object({}),
   },
   focus_window: {
@@ -36,30 +104,228 @@ export const toolSchemas = {
       window: z.string().describe('Window label to focus'),
     }),
   },
+  create_window: {
+    name: 'create_window',
+    description: 'Open a new window',
+    inputSchema: z.object({
+      label: z.string().describe('New window label'),
+      url: z.string().optional().describe('App path or external URL (default: index.html)'),
+      title: z.string().optional().describe('Window title'),
+      width: z.number().optional().describe('Width in logical pixels'),
+      height: z.number().optional().describe('Height in logical pixels'),
+    }),
+  },
+  manage_window: {
+    name: 'manage_window',
+    description: 'Resize, move, minimize, maximize, fullscreen, show, hide or close a window. Returns resulting window state.',
+    inputSchema: z.object({
+      action: z.enum([
+        'resize', 'move', 'minimize', 'maximize', 'unmaximize',
+        'fullscreen', 'exit_fullscreen', 'show', 'hide', 'close',
+      ]).describe('Window action'),
+      window: z.string().optional().describe('Window label (default: focused window; required for close)'),
+      width: z.number().optional().describe('Width in logical pixels (resize)'),
+      height: z.number().optional().describe('Height in logical pixels (resize)'),
+      x: z.number().optional().describe('X in logical pixels (move)'),
+      y: z.number().optional().describe('Y in logical pixels (move)'),
+    }),
+  },
+  wait_for_window: {
+    name: 'wait_for_window',
+    description: 'Wait until a window matching label/title/URL exists',
+    inputSchema: z.object({
+      label: z.string().optional().describe('Exact window label'),
+      title: z.string().optional().describe('Title substring'),
+      url: z.string().optional().describe('URL substring'),
+      since: z.number().optional().describe('Only windows created after this get_window_events cursor (default: created after the call; 0 includes earlier windows)'),
+      timeout_ms: z.number().optional().describe('Timeout in ms (default: 10000)'),
+    }),
+  },
+  intercept_popups: {
+    name: 'intercept_popups',
+    description: 'Intercept window.open and target="_blank" links: report (record and open), redirect (open in same webview), or off',
+    inputSchema: z.object({
+      mode: z.enum(['report', 'redirect', 'off']).describe('Popup handling mode'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  get_popups: {
+    name: 'get_popups',
+    description: 'Get popup requests recorded by intercept_popups',
+    inputSchema: z.object({
+      clear: z.boolean().optional().default(false).describe('Clear popups after reading'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  get_window_events: {
+    name: 'get_window_events',
+    description: 'Get window lifecycle events (created, focused, blurred, resized, moved, close_requested, destroyed, theme_changed, scale_factor_changed)',
+    inputSchema: z.object({
+      since: z.number().optional().describe('Cursor from a previous call (default: all events)'),
+      window: z.string().optional().describe('Only events for this window label'),
+      clear: z.boolean().optional().default(false).describe('Clear events after reading'),
+    }),
+  },
   snapshot: {
     name: 'snapshot',
-    description: 'Get accessibility tree (returns ref numbers for click/fill)',
+    description: 'Get accessibility tree (returns ref numbers for click/fill; an element keeps its ref across snapshots while it stays in the page) and a snapshot id',
     inputSchema: z.object({
+      format: z.enum(['text', 'json']).optional().describe('text outline or json node tree (default: text)'),
+      root: z.union([z.number(), z.string()]).optional().describe('Ref or locator of the element to snapshot instead of the whole page'),
+      max_depth: z.number().int().min(1).optional().describe('Levels to return; deeper nodes are counted on their ancestor'),
+      max_nodes: z.number().int().min(1).optional().describe('Nodes to return before truncating with a continuation cursor, also limits diff entries (default: 500)'),
+      cursor: z.string().optional().describe('Cursor from a truncated snapshot, to get its next nodes'),
+      interactive_only: z.boolean().optional().describe('Only interactive elements (default: false)'),
+      include_bounds: z.boolean().optional().describe('Add bounding boxes in viewport CSS pixels (default: false)'),
+      since: z.string().optional().describe('Snapshot id to diff against: returns only added (+), removed (-) and changed (~) nodes'),
       window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
     }),
   },
   click: {
     name: 'click',
-    description: 'Click element by ref or selector',
+    description: 'Click element by ref or selector, once it is visible, stable, enabled and not covered',
     inputSchema: z.object({
       ref: z.number().optional().describe('Ref from snapshot'),
-      selector: z.string().optional().describe('CSS selector'),
+      selector: locatorSchema,
+      force: z.boolean().optional().describe('Skip the visible/stable/enabled/hit-target checks'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element to become actionable (default: 5000)'),
       window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  click_at: {
+    name: 'click_at',
+    description: 'Click at a point of the latest screenshot (image pixels)',
+    inputSchema: z.object({
+      x: z.number().describe('X in screenshot pixels'),
+      y: z.number().describe('Y in screenshot pixels'),
+      transform: imageTransformSchema.optional().describe('Transform of the screenshot (default: latest screenshot)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  hover: {
+    name: 'hover',
+    description: 'Move the mouse onto an element (pointerover/enter/move events)',
+    inputSchema: z.object({
+      ref: z.number().optional().describe('Ref from snapshot'),
+      selector: locatorSchema,
+      force: z.boolean().optional().describe('Skip the visible/stable/enabled/hit-target checks'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element to become actionable (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  dblclick: {
+    name: 'dblclick',
+    description: 'Double-click an element with the full pointer/mouse event sequence',
+    inputSchema: z.object({
+      ref: z.number().optional().describe('Ref from snapshot'),
+      selector: locatorSchema,
+      force: z.boolean().optional().describe('Skip the visible/stable/enabled/hit-target checks'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element to become actionable (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  right_click: {
+    name: 'right_click',
+    description: 'Right-click an element (pointerdown/mousedown/contextmenu/mouseup)',
+    inputSchema: z.object({
+      ref: z.number().optional().describe('Ref from snapshot'),
+      selector: locatorSchema,
+      force: z.boolean().optional().describe('Skip the visible/stable/enabled/hit-target checks'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element to become actionable (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  mouse_down: {
+    name: 'mouse_down',
+    description: 'Press a mouse button at a point of the latest screenshot (image pixels)',
+    inputSchema: z.object({
+      x: z.number().describe('X in screenshot pixels'),
+      y: z.number().describe('Y in screenshot pixels'),
+      button: z.enum(['left', 'middle', 'right']).optional().describe('Mouse button (default: left)'),
+      transform: imageTransformSchema.optional().describe('Transform of the screenshot (default: latest screenshot)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  mouse_move: {
+    name: 'mouse_move',
+    description: 'Move the mouse to a point of the latest screenshot (image pixels); pressed buttons stay pressed',
+    inputSchema: z.object({
+      x: z.number().describe('X in screenshot pixels'),
+      y: z.number().describe('Y in screenshot pixels'),
+      button: z.enum(['left', 'middle', 'right']).optional().describe('Mouse button (default: left)'),
+      transform: imageTransformSchema.optional().describe('Transform of the screenshot (default: latest screenshot)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  mouse_up: {
+    name: 'mouse_up',
+    description: 'Release a mouse button at a point of the latest screenshot (image pixels)',
+    inputSchema: z.object({
+      x: z.number().describe('X in screenshot pixels'),
+      y: z.number().describe('Y in screenshot pixels'),
+      button: z.enum(['left', 'middle', 'right']).optional().describe('Mouse button (default: left)'),
+      transform: imageTransformSchema.optional().describe('Transform of the screenshot (default: latest screenshot)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  drag: {
+    name: 'drag',
+    description: 'Drag one element onto another (HTML drag-and-drop with DataTransfer for draggable sources, pointer moves otherwise)',
+    inputSchema: z.object({
+      from_ref: z.number().optional().describe('Ref of the element to drag'),
+      from_selector: z.string().optional().describe('Locator of the element to drag'),
+      to_ref: z.number().optional().describe('Ref of the drop target'),
+      to_selector: z.string().optional().describe('Locator of the drop target'),
+      steps: z.number().int().min(1).max(200).optional().describe('Intermediate moves, one per animation frame (default: 10)'),
+      force: z.boolean().optional().describe('Skip the visible/stable/enabled/hit-target checks'),
+      timeout_ms: z.number().optional().describe('How long to wait for each element to become actionable (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  element_at: {
+    name: 'element_at',
+    description: 'Describe the element at a point of the latest screenshot (image pixels)',
+    inputSchema: z.object({
+      x: z.number().describe('X in screenshot pixels'),
+      y: z.number().describe('Y in screenshot pixels'),
+      transform: imageTransformSchema.optional().describe('Transform of the screenshot (default: latest screenshot)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  locate: {
+    name: 'locate',
+    description: 'List the elements matching a locator (role, name, ref, visibility and rect), to check a locator before acting on it',
+    inputSchema: z.object({
+      selector: z.string().describe('Locator: CSS, role=button[name="Save"], text=Save, label=Email, testid=submit, xpath=//button, nth=<index>, chained with >>'),
+      limit: z.number().int().min(1).optional().describe('Maximum elements to describe (default: 20)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
     }),
   },
   fill: {
     name: 'fill',
-    description: 'Fill input by ref or selector',
+    description: 'Fill input, textarea or contenteditable by ref or selector, once it is visible, enabled and editable. Date/time/color inputs take their value format (2024-01-31, 13:45, #ff0000)',
     inputSchema: z.object({
       ref: z.number().optional().describe('Ref from snapshot'),
-      selector: z.string().optional().describe('CSS selector'),
+      selector: locatorSchema,
       value: z.string().describe('Value'),
+      force: z.boolean().optional().describe('Skip the visible/stable/enabled/hit-target checks'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element to become actionable (default: 5000)'),
       window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
     }),
   },
   press_key: {
@@ -68,6 +334,68 @@ export const toolSchemas = {
     inputSchema: z.object({
       key: z.string().describe('Key name'),
       window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  select_option: {
+    name: 'select_option',
+    description: 'Select options of a <select> by value, label or index (several for multi-selects)',
+    inputSchema: z.object({
+      ref: z.number().optional().describe('Ref from snapshot'),
+      selector: locatorSchema,
+      values: z.union([z.union([z.string(), z.object({ value: z.string().optional(), label: z.string().optional(), index: z.number().int().optional() })]), z.array(z.union([z.string(), z.object({ value: z.string().optional(), label: z.string().optional(), index: z.number().int().optional() })]))]).describe('Option value or label, { value, label, index }, or an array of them ([] deselects all)'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element and options (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  set_checked: {
+    name: 'set_checked',
+    description: 'Check or uncheck a checkbox, radio button or ARIA checkbox/switch (no-op if already in that state)',
+    inputSchema: z.object({
+      ref: z.number().optional().describe('Ref from snapshot'),
+      selector: locatorSchema,
+      checked: z.boolean().optional().describe('Desired state (default: true)'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element to become visible and enabled (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  set_input_files: {
+    name: 'set_input_files',
+    description: 'Set the files of an <input type="file"> from local paths (works on hidden inputs)',
+    inputSchema: z.object({
+      ref: z.number().optional().describe('Ref from snapshot'),
+      selector: locatorSchema,
+      paths: z.array(z.string()).describe('Local file paths, relative to the project root unless absolute; at most 5 MB in total ([] clears the input)'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  press: {
+    name: 'press',
+    description: 'Press a key or chord (e.g. Enter, Tab, Control+Shift+K) with real key codes and default actions (focus traversal, form submit, editing)',
+    inputSchema: z.object({
+      key: z.string().describe('Key or chord: modifiers joined with +, e.g. Control+A, Shift+Tab, Meta+Enter'),
+      ref: z.number().optional().describe('Ref from snapshot to focus first (default: focused element)'),
+      selector: z.string().optional().describe('Locator to focus first (default: focused element)'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element to become visible and enabled (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  type_text: {
+    name: 'type_text',
+    description: 'Type text character by character with key events (\\n presses Enter)',
+    inputSchema: z.object({
+      text: z.string().describe('Text to type'),
+      delay_ms: z.number().optional().describe('Delay between characters (default: 0)'),
+      ref: z.number().optional().describe('Ref from snapshot to focus first (default: focused element)'),
+      selector: z.string().optional().describe('Locator to focus first (default: focused element)'),
+      timeout_ms: z.number().optional().describe('How long to wait for the element to become visible and enabled (default: 5000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
     }),
   },
   evaluate_script: {
@@ -76,13 +404,111 @@ export const toolSchemas = {
     inputSchema: z.object({
       script: z.string().describe('JS code'),
       window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
     }),
   },
   screenshot: {
     name: 'screenshot',
     description: 'Take screenshot',
     inputSchema: z.object({
+      full_page: z.boolean().optional().describe('Capture the whole scrollable page (default: false)'),
+      scroll_container: z.string().optional().describe('Locator of the element to scroll for full_page (default: auto-detect)'),
+      annotate: z.boolean().optional().describe('Draw numbered boxes for snapshot refs and return their rects (default: false)'),
+      ...imageOutputSchema,
+      ...captureSetupSchema,
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  screenshot_element: {
+    name: 'screenshot_element',
+    description: 'Take screenshot of a single element by ref or selector',
+    inputSchema: z.object({
+      ref: z.number().optional().describe('Ref from snapshot'),
+      selector: locatorSchema,
+      ...imageOutputSchema,
+      ...captureSetupSchema,
       window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  compare_screenshot: {
+    name: 'compare_screenshot',
+    description: 'Compare a native screenshot against a baseline in tests/__screenshots__ (created if missing); differences are written to <name>.diff.png next to it',
+    inputSchema: z.object({
+      name: z.string().describe('Baseline name, e.g. "settings/dark-mode"'),
+      update: z.boolean().optional().describe('Overwrite the baseline with the current screenshot (default: false)'),
+      threshold: z.number().min(0).max(1).optional().describe('Per-pixel color tolerance 0-1 (default: 0.1)'),
+      anti_aliasing: z.boolean().optional().describe('Ignore anti-aliasing differences (default: true)'),
+      max_diff_percentage: z.number().min(0).max(100).optional().describe('Mismatch percentage that still passes (default: 0)'),
+      full_page: z.boolean().optional().describe('Capture the whole scrollable page (default: false)'),
+      scroll_container: z.string().optional().describe('Locator of the element to scroll for full_page (default: auto-detect)'),
+      max_width: z.number().optional().describe('Maximum width in pixels (default: 1920)'),
+      max_height: z.number().optional().describe('Maximum height in pixels (default: 1080)'),
+      scale: z.number().positive().optional().describe('Scale factor applied before max size'),
+      ...captureSetupSchema,
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  wait_for: {
+    name: 'wait_for',
+    description: 'Wait for an element state, text, URL or JS predicate (give exactly one)',
+    inputSchema: z.object({
+      selector: z.string().optional().describe('Locator of the element'),
+      ref: z.number().optional().describe('Ref from snapshot'),
+      state: z.enum(['attached', 'detached', 'visible', 'hidden', 'enabled']).optional().describe('Element state (default: visible)'),
+      text: z.string().optional().describe('Text to appear in the page'),
+      url: z.string().optional().describe('URL substring, or /regex/flags'),
+      predicate: z.string().optional().describe('JS expression or function that must return truthy'),
+      timeout_ms: z.number().optional().describe('Timeout in milliseconds (default: 10000)'),
+      poll_ms: z.number().optional().describe('Polling interval in milliseconds (default: 100)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  wait_for_idle: {
+    name: 'wait_for_idle',
+    description: 'Wait until no fetch, XHR or Tauri IPC calls are in flight for a quiet period (like networkidle). Requires initMcpBridge()',
+    inputSchema: z.object({
+      idle_ms: z.number().optional().describe('Quiet period with nothing in flight (default: 500)'),
+      timeout_ms: z.number().optional().describe('Timeout in milliseconds (default: 10000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  wait_for_visual_stable: {
+    name: 'wait_for_visual_stable',
+    description: 'Wait until repeated native captures of the window (or an element) stop changing',
+    inputSchema: z.object({
+      ref: z.number().optional().describe('Ref from snapshot to watch instead of the whole window'),
+      selector: z.string().optional().describe('Locator to watch instead of the whole window'),
+      threshold: z.number().min(0).max(1).optional().describe('Per-pixel color tolerance 0-1 (default: 0.1)'),
+      max_diff_percentage: z.number().min(0).max(100).optional().describe('Changed pixel percentage still counted as unchanged (default: 0.1)'),
+      stable_frames: z.number().int().min(1).optional().describe('Consecutive unchanged frames required (default: 3)'),
+      interval_ms: z.number().optional().describe('Delay between captures (default: 100)'),
+      timeout_ms: z.number().optional().describe('Timeout in milliseconds (default: 10000)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
+    }),
+  },
+  start_recording: {
+    name: 'start_recording',
+    description: 'Start recording a window as a sequence of native captures',
+    inputSchema: z.object({
+      fps: z.number().min(1).max(30).optional().describe('Frames per second (default: 5)'),
+      max_duration_ms: z.number().optional().describe('Stop capturing after this long (default: 60000)'),
+      max_width: z.number().optional().describe('Maximum frame width in pixels (default: 1280)'),
+      max_height: z.number().optional().describe('Maximum frame height in pixels (default: 720)'),
+      window: z.string().optional().describe('Window label (default: focused window)'),
+    }),
+  },
+  stop_recording: {
+    name: 'stop_recording',
+    description: 'Stop the recording and write it as an animated PNG or a directory of PNG frames',
+    inputSchema: z.object({
+      format: z.enum(['apng', 'png']).optional().describe('apng: animated PNG file, png: numbered frames in a directory (default: apng)'),
+      path: z.string().optional().describe('Output file or directory, relative to the project root (default: temp directory)'),
     }),
   },
   navigate: {
@@ -91,6 +517,7 @@ export const toolSchemas = {
     inputSchema: z.object({
       url: z.string().describe('URL'),
       window: z.string().optional().describe('Window label (default: focused window)'),
+      webview: z.string().optional().describe('Webview label (default: window\'s main webview)'),
     }),
   },
   get_logs: {
@@ -192,7 +619,80 @@ export function createToolHandlers(tauriManager: TauriManager, socketManager: So
       };
     },
 
-    snapshot: async (args: { window?: string }) => {
+    create_window: async (args: { label: string; url?: string; title?: string; width?: number; height?: number }) => {
+      const result = await socketManager.createWindow(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    manage_window: async (args: { action: WindowAction; window?: string; width?: number; height?: number; x?: number; y?: number }) => {
+      const { action, ...options } = args;
+      const result = await socketManager.manageWindow(action, options);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    wait_for_window: async (args: { label?: string; title?: string; url?: string; since?: number; timeout_ms?: number }) => {
+      const result = await socketManager.waitForWindow(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    intercept_popups: async (args: { mode: 'report' | 'redirect' | 'off'; window?: string; webview?: string }) => {
+      const result = await socketManager.interceptPopups(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: result,
+          },
+        ],
+      };
+    },
+
+    get_popups: async (args: { clear?: boolean; window?: string; webview?: string }) => {
+      const result = await socketManager.getPopups(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    get_window_events: async (args: { since?: number; window?: string; clear?: boolean }) => {
+      const result = await socketManager.getWindowEvents(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    snapshot: async (args: SnapshotOptions) => {
       const result = await socketManager.snapshot(args);
       return {
         content: [
@@ -204,7 +704,7 @@ export function createToolHandlers(tauriManager: TauriManager, socketManager: So
       };
     },
 
-    click: async (args: { ref?: number; selector?: string; window?: string }) => {
+    click: async (args: { ref?: number; selector?: string; force?: boolean; timeout_ms?: number; window?: string; webview?: string }) => {
       if (!args.ref && !args.selector) {
         throw new Error('Either ref or selector must be provided');
       }
@@ -219,7 +719,136 @@ export function createToolHandlers(tauriManager: TauriManager, socketManager: So
       };
     },
 
-    fill: async (args: { ref?: number; selector?: string; value: string; window?: string }) => {
+    click_at: async (args: { x: number; y: number; transform?: ImageTransform; window?: string; webview?: string }) => {
+      const result = await socketManager.clickAt(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    hover: async (args: ElementActionOptions) => {
+      if (!args.ref && !args.selector) {
+        throw new Error('Either ref or selector must be provided');
+      }
+      const result = await socketManager.pointerAction('hover', args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: result,
+          },
+        ],
+      };
+    },
+
+    dblclick: async (args: ElementActionOptions) => {
+      if (!args.ref && !args.selector) {
+        throw new Error('Either ref or selector must be provided');
+      }
+      const result = await socketManager.pointerAction('dblclick', args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: result,
+          },
+        ],
+      };
+    },
+
+    right_click: async (args: ElementActionOptions) => {
+      if (!args.ref && !args.selector) {
+        throw new Error('Either ref or selector must be provided');
+      }
+      const result = await socketManager.pointerAction('right_click', args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: result,
+          },
+        ],
+      };
+    },
+
+    mouse_down: async (args: MouseOptions) => {
+      const result = await socketManager.mouse('mouse_down', args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    mouse_move: async (args: MouseOptions) => {
+      const result = await socketManager.mouse('mouse_move', args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    mouse_up: async (args: MouseOptions) => {
+      const result = await socketManager.mouse('mouse_up', args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    drag: async (args: DragOptions) => {
+      const result = await socketManager.drag(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    element_at: async (args: { x: number; y: number; transform?: ImageTransform; window?: string; webview?: string }) => {
+      const result = await socketManager.elementAt(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    locate: async (args: { selector: string; limit?: number; window?: string; webview?: string }) => {
+      const result = await socketManager.locate(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    fill: async (args: { ref?: number; selector?: string; value: string; force?: boolean; timeout_ms?: number; window?: string; webview?: string }) => {
       if (!args.ref && !args.selector) {
         throw new Error('Either ref or selector must be provided');
       }
@@ -234,8 +863,69 @@ export function createToolHandlers(tauriManager: TauriManager, socketManager: So
       };
     },
 
-    press_key: async (args: { key: string; window?: string }) => {
-      const result = await socketManager.pressKey(args.key, args.window);
+    press_key: async (args: { key: string; window?: string; webview?: string }) => {
+      const result = await socketManager.pressKey(args.key, args.window, args.webview);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: result,
+          },
+        ],
+      };
+    },
+
+    select_option: async (args: SelectOptionOptions) => {
+      const values = await socketManager.selectOption(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: `Selected ${JSON.stringify(values)}`,
+          },
+        ],
+      };
+    },
+
+    set_checked: async (args: SetCheckedOptions) => {
+      const result = await socketManager.setChecked(args);
+      const state = result.checked ? 'Checked' : 'Unchecked';
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: result.changed ? state : `Already ${state.toLowerCase()}`,
+          },
+        ],
+      };
+    },
+
+    set_input_files: async (args: SetInputFilesOptions) => {
+      const files = await socketManager.setInputFiles(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: files.length ? `Set files: ${files.map((f) => `${f.name} (${f.size} bytes)`).join(', ')}` : 'Cleared files',
+          },
+        ],
+      };
+    },
+
+    press: async (args: PressOptions) => {
+      const result = await socketManager.press(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: result,
+          },
+        ],
+      };
+    },
+
+    type_text: async (args: TypeTextOptions) => {
+      const result = await socketManager.typeText(args);
       return {
         content: [
           {
@@ -246,8 +936,8 @@ export function createToolHandlers(tauriManager: TauriManager, socketManager: So
       };
     },
 
-    evaluate_script: async (args: { script: string; window?: string }) => {
-      const result = await socketManager.evaluateScript(args.script, args.window);
+    evaluate_script: async (args: { script: string; window?: string; webview?: string }) => {
+      const result = await socketManager.evaluateScript(args.script, args.window, args.webview);
       return {
         content: [
           {
@@ -258,21 +948,95 @@ export function createToolHandlers(tauriManager: TauriManager, socketManager: So
       };
     },
 
-    screenshot: async (args: { window?: string }) => {
+    screenshot: async (args: ScreenshotOptions) => {
       const result = await socketManager.screenshot(args);
+      return imageContent(result);
+    },
+
+    screenshot_element: async (args: { ref?: number; selector?: string } & ScreenshotOptions) => {
+      if (!args.ref && !args.selector) {
+        throw new Error('Either ref or selector must be provided');
+      }
+      const result = await socketManager.screenshotElement(args);
+      return imageContent(result);
+    },
+
+    compare_screenshot: async (args: CompareScreenshotOptions) => {
+      // The diff image (mismatches in red, tolerated anti-aliasing in yellow)
+      // is written next to the baseline; `diff` is its path
+      const result = await socketManager.compareScreenshot(args);
       return {
         content: [
           {
-            type: 'image' as const,
-            data: result.data,
-            mimeType: result.mimeType,
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
+          },
+        ],
+      };
+    },
+
+    wait_for: async (args: WaitForOptions) => {
+      const result = await socketManager.waitFor(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: `Condition met after ${result.elapsed_ms}ms: ${JSON.stringify(result.state)}`,
+          },
+        ],
+      };
+    },
+
+    wait_for_idle: async (args: IdleOptions) => {
+      const result = await socketManager.waitForIdle(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: `Idle after ${result.elapsed_ms}ms`,
+          },
+        ],
+      };
+    },
+
+    wait_for_visual_stable: async (args: VisualStableOptions) => {
+      const result = await socketManager.waitForVisualStable(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: `Visually stable after ${result.elapsed_ms}ms (${result.frames} frames)`,
+          },
+        ],
+      };
+    },
+
+    start_recording: async (args: RecordingOptions) => {
+      const result = await socketManager.startRecording(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: `Recording window '${result.window}' at ${result.fps} fps`,
+          },
+        ],
+      };
+    },
+
+    stop_recording: async (args: { format?: 'apng' | 'png'; path?: string }) => {
+      const result = await socketManager.stopRecording(args);
+      return {
+        content: [
+          {
+            type: 'text' as const,
+            text: JSON.stringify(result, null, 2),
           },
         ],
       };
     },
 
-    navigate: async (args: { url: string; window?: string }) => {
-      const result = await socketManager.navigate(args.url, args.window);
+    navigate: async (args: { url: string; window?: string; webview?: string }) => {
+      const result = await socketManager.navigate(args.url, args.window, args.webview);
       return {
         content: [
           {
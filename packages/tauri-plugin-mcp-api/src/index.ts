@@ -1,5 +1,5 @@
 import { invoke, Channel } from '@tauri-apps/api/core';
-import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
+import { getCurrentWebview } from '@tauri-apps/api/webview';
 
 // Original function references (preserved across HMR reloads)
 let originalConsole: Record<string, (...args: unknown[]) => void> | null = null;
@@ -53,6 +53,16 @@ interface NetworkLogEntry {
   timestamp: number;
 }
 
+/**
+ * Network request or Tauri IPC call that has not completed yet
+ */
+interface PendingRequest {
+  type: 'fetch' | 'xhr' | 'ipc';
+  method: string;
+  url: string;
+  timestamp: number;
+}
+
 /**
  * Build log entry (Vite/TypeScript errors)
  */
@@ -93,6 +103,20 @@ interface ViteHmrFullReloadPayload {
   path?: string;
 }
 
+/**
+ * Snapshot refs, persistent per element
+ *
+ * `ids` is a WeakMap so refs never keep removed elements alive; `nodes` maps
+ * refs back to their elements through WeakRefs. Refs count up from a random
+ * per-document `base`, so refs from an earlier page load don't resolve.
+ */
+interface McpRefRegistry {
+  base: number;
+  next: number;
+  ids: WeakMap<Element, number>;
+  nodes: Map<number, { deref(): Element | undefined }>;
+}
+
 /**
  * MCP Bridge state
  */
@@ -105,10 +129,14 @@ declare global {
   interface Window {
     __MCP_BRIDGE__: McpBridgeState;
     __MCP_EVAL__: (requestId: string, script: string) => Promise<void>;
-    __MCP_REF_MAP__: Map<number, Element>;
+    __MCP_REFS__?: McpRefRegistry;
     __MCP_WINDOW_LABEL__: string;
+    __MCP_WEBVIEW_LABEL__: string;
     __MCP_CONSOLE_LOGS__: ConsoleLogEntry[];
     __MCP_NETWORK_LOGS__: NetworkLogEntry[];
+    __MCP_PENDING_REQUESTS__: Map<number, PendingRequest>;
+    __MCP_REQUEST_TRACKING__?: boolean;
+    __MCP_LAST_REQUEST_ACTIVITY__: number;
     __MCP_BUILD_LOGS__: BuildLogEntry[];
     __MCP_HMR_UPDATES__: HmrUpdateEntry[];
     __MCP_HMR_STATUS__: 'connected' | 'disconnected' | 'unknown';
@@ -142,21 +170,22 @@ export async function initMcpBridge(): Promise<void> {
     channel,
   };
 
-  // Initialize ref map for accessibility tree
-  window.__MCP_REF_MAP__ = new Map();
-
-  // Get and store window label for multi-window support
+  // Get and store window/webview labels for multi-window and multi-webview support
   try {
-    const currentWindow = getCurrentWebviewWindow();
-    window.__MCP_WINDOW_LABEL__ = currentWindow.label;
+    const currentWebview = getCurrentWebview();
+    window.__MCP_WINDOW_LABEL__ = currentWebview.window.label;
+    window.__MCP_WEBVIEW_LABEL__ = currentWebview.label;
   } catch {
-    // Fallback if webviewWindow API is not available
+    // Fallback if webview API is not available
     window.__MCP_WINDOW_LABEL__ = 'main';
+    window.__MCP_WEBVIEW_LABEL__ = 'main';
   }
 
   // Initialize log storage (preserve existing logs across HMR reloads)
   window.__MCP_CONSOLE_LOGS__ = window.__MCP_CONSOLE_LOGS__ || [];
   window.__MCP_NETWORK_LOGS__ = window.__MCP_NETWORK_LOGS__ || [];
+  window.__MCP_PENDING_REQUESTS__ = window.__MCP_PENDING_REQUESTS__ || new Map();
+  window.__MCP_LAST_REQUEST_ACTIVITY__ = window.__MCP_LAST_REQUEST_ACTIVITY__ || Date.now();
   window.__MCP_BUILD_LOGS__ = window.__MCP_BUILD_LOGS__ || [];
   window.__MCP_HMR_UPDATES__ = window.__MCP_HMR_UPDATES__ || [];
   window.__MCP_HMR_STATUS__ = window.__MCP_HMR_STATUS__ || 'unknown';
@@ -197,8 +226,10 @@ export async function initMcpBridge(): Promise<void> {
     await invoke('plugin:mcp|eval_result', { result });
   };
 
-  // Register the bridge with the Rust plugin
-  await invoke('plugin:mcp|register_bridge');
+  // Register the bridge with the Rust plugin, which returns the page scripts
+  // commands rely on (locator engine, screenshot rasterizer), installed once per page
+  const pageScripts = await invoke<string>('plugin:mcp|register_bridge');
+  new Function(pageScripts)();
 
   // Register HMR cleanup handler
   if (import.meta.hot) {
@@ -317,6 +348,45 @@ function serializeArg(arg: unknown): unknown {
   }
 }
 
+let nextPendingRequestId = 0;
+
+/**
+ * Record the start of a network request or IPC call, returning its tracking ID
+ *
+ * Returns null when the auto-injected bridge already tracks requests beneath
+ * these wrappers, so nothing is counted twice.
+ */
+function trackRequest(request: Omit<PendingRequest, 'timestamp'>): number | null {
+  if (window.__MCP_REQUEST_TRACKING__) {
+    return null;
+  }
+  const id = ++nextPendingRequestId;
+  window.__MCP_PENDING_REQUESTS__.set(id, { ...request, timestamp: Date.now() });
+  window.__MCP_LAST_REQUEST_ACTIVITY__ = Date.now();
+  return id;
+}
+
+/**
+ * Record the completion of a tracked request
+ */
+function untrackRequest(id: number): void {
+  if (window.__MCP_PENDING_REQUESTS__.delete(id)) {
+    window.__MCP_LAST_REQUEST_ACTIVITY__ = Date.now();
+  }
+}
+
+/**
+ * Tauri command name if the URL is a Tauri IPC request, otherwise null
+ *
+ * `invoke` cannot be wrapped (Tauri defines it read-only), but its custom
+ * protocol transport goes through `fetch` to `ipc://localhost/<command>`
+ * (`http://ipc.localhost/<command>` on Windows and Android).
+ */
+function ipcCommand(url: string): string | null {
+  const match = url.match(/^(?:ipc:\/\/localhost|https?:\/\/ipc\.localhost)\/([^?#]+)/);
+  return match ? decodeURIComponent(match[1]) : null;
+}
+
 /**
  * Set up fetch and XMLHttpRequest capture
  */
@@ -338,6 +408,16 @@ function setupNetworkCapture(): void {
     const method = init?.method || 'GET';
     const startTime = Date.now();
 
+    // Track as pending, except the bridge's own IPC (eval results), which
+    // would otherwise keep the app from ever looking idle
+    const command = ipcCommand(url);
+    let pendingId: number | null = null;
+    if (command === null) {
+      pendingId = trackRequest({ type: 'fetch', method, url });
+    } else if (!command.startsWith('plugin:mcp|')) {
+      pendingId = trackRequest({ type: 'ipc', method: 'invoke', url: command });
+    }
+
     try {
       const response = await originalFetch!(input, init);
 
@@ -372,6 +452,10 @@ function setupNetworkCapture(): void {
       }
 
       throw error;
+    } finally {
+      if (pendingId !== null) {
+        untrackRequest(pendingId);
+      }
     }
   };
 
@@ -429,6 +513,12 @@ function setupNetworkCapture(): void {
     this.addEventListener('load', handleEnd);
     this.addEventListener('error', handleError);
 
+    // loadend follows load, error, abort and timeout alike
+    const pendingId = trackRequest({ type: 'xhr', method: xhr.__mcp_method || 'GET', url: xhr.__mcp_url || '' });
+    if (pendingId !== null) {
+      this.addEventListener('loadend', () => untrackRequest(pendingId), { once: true });
+    }
+
     return xhrSendRef.call(this, body);
   };
 }
@@ -0,0 +1,172 @@
+//! Set-of-marks annotation: numbered boxes for snapshot refs drawn on screenshots
+
+use image::{Rgba, RgbaImage};
+
+use super::compare::decode_screenshot;
+use super::screenshot::{encode_image, ImageOptions, ImageTransform, OutputFormat, Rect};
+
+/// Box and label colors, picked by ref number so neighbors differ
+const PALETTE: [[u8; 3]; 8] = [
+    [230, 25, 75],
+    [60, 180, 75],
+    [0, 130, 200],
+    [245, 130, 48],
+    [145, 30, 180],
+    [0, 128, 128],
+    [240, 50, 230],
+    [128, 128, 0],
+];
+
+/// 3x5 bitmap digits, one row per byte (low three bits, left to right)
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Size of one digit pixel in image pixels
+const GLYPH_SCALE: u32 = 2;
+const LABEL_PADDING: u32 = 2;
+
+/// A snapshot ref and its viewport rect in CSS pixels
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RefRect {
+    #[serde(rename = "ref")]
+    pub ref_num: u32,
+    #[serde(flatten)]
+    pub rect: Rect,
+}
+
+/// Draw numbered boxes for `refs` onto a screenshot result
+///
+/// Boxes are drawn after resizing so labels stay legible, placed with the
+/// result's `transform`. Adds an `annotations` list with each ref's rect in
+/// the returned image's pixels, and writes the image to the options' `path`
+/// if one is set.
+pub fn annotate_screenshot(
+    result: &mut serde_json::Value,
+    refs: &[RefRect],
+    options: &ImageOptions,
+) -> Result<(), String> {
+    let transform: ImageTransform = serde_json::from_value(result["transform"].clone())
+        .map_err(|e| format!("Screenshot has no coordinate transform: {}", e))?;
+    let mut image = decode_screenshot(result)?;
+    let (width, height) = image.dimensions();
+
+    let mut annotations = Vec::new();
+    for item in refs {
+        let rect = transform.css_to_image(&item.rect);
+        let left = rect.x.max(0.0).round() as u32;
+        let top = rect.y.max(0.0).round() as u32;
+        let right = ((rect.x + rect.width).round().max(0.0) as u32).min(width);
+        let bottom = ((rect.y + rect.height).round().max(0.0) as u32).min(height);
+        if right <= left || bottom <= top {
+            continue;
+        }
+
+        let [r, g, b] = PALETTE[item.ref_num as usize % PALETTE.len()];
+        let color = Rgba([r, g, b, 255]);
+        draw_box(&mut image, left, top, right, bottom, color);
+        draw_label(&mut image, item.ref_num, left, top, color);
+        annotations.push(serde_json::json!({
+            "ref": item.ref_num,
+            "x": left,
+            "y": top,
+            "width": right - left,
+            "height": bottom - top,
+        }));
+    }
+
+    // Re-encode in the original format without resizing again
+    let format = options.format.or_else(|| {
+        result["data"]
+            .as_str()
+            .and_then(|data| data.strip_prefix("data:image/jpeg"))
+            .map(|_| OutputFormat::Jpeg)
+    });
+    let encode_options = ImageOptions {
+        format,
+        quality: options.quality,
+        path: options.path.clone(),
+        ..Default::default()
+    };
+    let encoded = encode_image(image, &encode_options, u32::MAX, u32::MAX)?;
+    // `data`, or `path`, `mimeType` and `bytes` when written to a file
+    if let (Some(object), Some(encoded)) = (result.as_object_mut(), encoded.as_object()) {
+        object.remove("data");
+        object.extend(encoded.clone());
+    }
+    result["annotations"] = serde_json::json!(annotations);
+    Ok(())
+}
+
+/// Draw a two pixel wide rectangle outline
+fn draw_box(image: &mut RgbaImage, left: u32, top: u32, right: u32, bottom: u32, color: Rgba<u8>) {
+    for y in top..bottom {
+        for x in left..right {
+            let edge = x < left + 2 || x + 2 >= right || y < top + 2 || y + 2 >= bottom;
+            if edge {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+/// Draw the ref number in white on a filled tag at the box's top-left corner
+///
+/// The tag sits above the box when there is room, otherwise inside it.
+fn draw_label(image: &mut RgbaImage, ref_num: u32, left: u32, top: u32, color: Rgba<u8>) {
+    let digits: Vec<usize> = ref_num
+        .to_string()
+        .bytes()
+        .map(|b| (b - b'0') as usize)
+        .collect();
+    let glyph_width = 3 * GLYPH_SCALE;
+    let glyph_height = 5 * GLYPH_SCALE;
+    let label_width =
+        digits.len() as u32 * (glyph_width + GLYPH_SCALE) - GLYPH_SCALE + 2 * LABEL_PADDING;
+    let label_height = glyph_height + 2 * LABEL_PADDING;
+    let label_top = if top >= label_height {
+        top - label_height
+    } else {
+        top
+    };
+
+    fill(image, left, label_top, label_width, label_height, color);
+    let white = Rgba([255, 255, 255, 255]);
+    for (i, digit) in digits.iter().enumerate() {
+        let glyph_left = left + LABEL_PADDING + i as u32 * (glyph_width + GLYPH_SCALE);
+        for (row, bits) in DIGITS[*digit].iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    fill(
+                        image,
+                        glyph_left + col * GLYPH_SCALE,
+                        label_top + LABEL_PADDING + row as u32 * GLYPH_SCALE,
+                        GLYPH_SCALE,
+                        GLYPH_SCALE,
+                        white,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Fill a rectangle, clipped to the image
+fn fill(image: &mut RgbaImage, left: u32, top: u32, width: u32, height: u32, color: Rgba<u8>) {
+    let right = left.saturating_add(width).min(image.width());
+    let bottom = top.saturating_add(height).min(image.height());
+    for y in top..bottom {
+        for x in left..right {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Visual regression: compare screenshots against stored baselines
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+/// Directory, relative to the project root, where baselines are stored
+pub const BASELINE_DIR: &str = "tests/__screenshots__";
+
+/// Maximum YIQ color distance between two pixels
+const MAX_YIQ_DELTA: f64 = 35215.0;
+
+/// Options controlling how screenshots are compared
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default)]
+pub struct CompareOptions {
+    /// Per-pixel color distance tolerance, 0 (exact) to 1 (anything matches)
+    pub threshold: f64,
+    /// Ignore pixels that look like anti-aliasing differences
+    pub anti_aliasing: bool,
+    /// Mismatch percentage at or below which the comparison passes
+    pub max_diff_percentage: f64,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.1,
+            anti_aliasing: true,
+            max_diff_percentage: 0.0,
+        }
+    }
+}
+
+impl CompareOptions {
+    /// Read comparison options from request params
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        let options: CompareOptions = if params.is_object() {
+            serde_json::from_value(params.clone())
+                .map_err(|e| format!("Invalid compare options: {}", e))?
+        } else {
+            CompareOptions::default()
+        };
+        if !(0.0..=1.0).contains(&options.threshold) {
+            return Err("threshold must be between 0 and 1".to_string());
+        }
+        if !(0.0..=100.0).contains(&options.max_diff_percentage) {
+            return Err("max_diff_percentage must be between 0 and 100".to_string());
+        }
+        Ok(options)
+    }
+}
+
+/// Options for waiting until consecutive captures stop changing
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default)]
+pub struct StabilityOptions {
+    /// Per-pixel color distance tolerance, as in [`CompareOptions`]
+    pub threshold: f64,
+    /// Frames differing in at most this percentage of pixels count as unchanged
+    pub max_diff_percentage: f64,
+    /// Consecutive unchanged frames required
+    pub stable_frames: u32,
+    /// Delay between captures
+    pub interval_ms: u64,
+    pub timeout_ms: u64,
+}
+
+impl Default for StabilityOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.1,
+            max_diff_percentage: 0.1,
+            stable_frames: 3,
+            interval_ms: 100,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+impl StabilityOptions {
+    /// Read stability options from request params
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        let options: StabilityOptions = if params.is_object() {
+            serde_json::from_value(params.clone())
+                .map_err(|e| format!("Invalid stability options: {}", e))?
+        } else {
+            StabilityOptions::default()
+        };
+        if !(0.0..=1.0).contains(&options.threshold) {
+            return Err("threshold must be between 0 and 1".to_string());
+        }
+        if !(0.0..=100.0).contains(&options.max_diff_percentage) {
+            return Err("max_diff_percentage must be between 0 and 100".to_string());
+        }
+        if options.stable_frames == 0 {
+            return Err("stable_frames must be at least 1".to_string());
+        }
+        Ok(options)
+    }
+}
+
+/// Resolve a baseline name to a PNG file under the project's baseline directory
+///
+/// Names may contain subdirectories but must stay inside the baseline directory.
+pub fn baseline_path(project_root: &Path, name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && name.split('/').all(|part| {
+            !part.is_empty()
+                && part != "."
+                && part != ".."
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+    if !valid {
+        return Err(format!(
+            "Invalid baseline name '{}': use letters, digits, '-', '_', '.' and '/'",
+            name
+        ));
+    }
+
+    let mut path = project_root.join(BASELINE_DIR).join(name);
+    if path.extension().is_none_or(|ext| ext != "png") {
+        path.set_extension("png");
+    }
+    Ok(path)
+}
+
+/// Decode the data URL of a screenshot result into an image
+pub fn decode_screenshot(result: &serde_json::Value) -> Result<RgbaImage, String> {
+    let data_url = result["data"]
+        .as_str()
+        .ok_or_else(|| "Screenshot result has no image data".to_string())?;
+    let base64_data = data_url
+        .split_once(";base64,")
+        .map_or(data_url, |(_, data)| data);
+    let bytes = BASE64
+        .decode(base64_data)
+        .map_err(|e| format!("Failed to decode image data: {}", e))?;
+    image::load_from_memory(&bytes)
+        .map(|image| image.to_rgba8())
+        .map_err(|e| format!("Failed to decode image: {}", e))
+}
+
+/// Write an image to a PNG file (baseline or diff), creating its directory
+pub fn save_baseline(path: &Path, image: &RgbaImage) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    image
+        .save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Load a baseline image, or `None` if it does not exist yet
+pub fn load_baseline(path: &Path) -> Result<Option<RgbaImage>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    image::open(path)
+        .map(|image| Some(image.to_rgba8()))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+/// Diff image file stored next to a baseline: `<name>.diff.png`
+pub fn diff_path(baseline_path: &Path) -> PathBuf {
+    baseline_path.with_extension("diff.png")
+}
+
+/// Compare a screenshot against its baseline
+///
+/// Pixels outside the overlap of differently sized images count as
+/// mismatches. When any pixel differs, a diff image is written to `diff_path`
+/// and its path returned as `diff`: mismatches in red, tolerated anti-aliasing
+/// in yellow, and unchanged pixels as a faded copy of the baseline. A stale
+/// diff from an earlier run is removed otherwise.
+pub fn compare_images(
+    baseline: &RgbaImage,
+    actual: &RgbaImage,
+    options: &CompareOptions,
+    diff_path: &Path,
+) -> Result<serde_json::Value, String> {
+    let width = baseline.width().max(actual.width());
+    let height = baseline.height().max(actual.height());
+    let max_delta = MAX_YIQ_DELTA * options.threshold * options.threshold;
+
+    let mut diff = RgbaImage::new(width, height);
+    let mut diff_pixels: u64 = 0;
+    let mut aa_pixels: u64 = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let (Some(a), Some(b)) = (
+                baseline.get_pixel_checked(x, y),
+                actual.get_pixel_checked(x, y),
+            ) else {
+                diff_pixels += 1;
+                diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+                continue;
+            };
+
+            if color_delta(a, b) <= max_delta {
+                diff.put_pixel(x, y, faded(a));
+            } else if options.anti_aliasing
+                && has_matching_neighbor(baseline, actual, x, y, max_delta)
+                && has_matching_neighbor(actual, baseline, x, y, max_delta)
+            {
+                aa_pixels += 1;
+                diff.put_pixel(x, y, Rgba([255, 255, 0, 255]));
+            } else {
+                diff_pixels += 1;
+                diff.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+        }
+    }
+
+    let total = (width as u64 * height as u64).max(1);
+    let diff_percentage = diff_pixels as f64 * 100.0 / total as f64;
+    let mut result = serde_json::json!({
+        "passed": diff_percentage <= options.max_diff_percentage,
+        "diff_pixels": diff_pixels,
+        "anti_aliased_pixels": aa_pixels,
+        "diff_percentage": diff_percentage,
+        "width": actual.width(),
+        "height": actual.height(),
+        "baseline_width": baseline.width(),
+        "baseline_height": baseline.height(),
+    });
+    if diff_pixels > 0 || aa_pixels > 0 {
+        save_baseline(diff_path, &diff)?;
+        result["diff"] = serde_json::json!(diff_path.display().to_string());
+    } else if diff_path.exists() {
+        std::fs::remove_file(diff_path)
+            .map_err(|e| format!("Failed to remove {}: {}", diff_path.display(), e))?;
+    }
+    Ok(result)
+}
+
+/// Percentage of pixels whose color distance exceeds `threshold`
+///
+/// A cheaper [`compare_images`] without anti-aliasing detection or a diff
+/// image. Images of different sizes differ completely.
+pub fn diff_percentage(a: &RgbaImage, b: &RgbaImage, threshold: f64) -> f64 {
+    if a.dimensions() != b.dimensions() {
+        return 100.0;
+    }
+    let max_delta = MAX_YIQ_DELTA * threshold * threshold;
+    let diff_pixels = a
+        .pixels()
+        .zip(b.pixels())
+        .filter(|(p, q)| color_delta(p, q) > max_delta)
+        .count();
+    let total = (a.width() as u64 * a.height() as u64).max(1);
+    diff_pixels as f64 * 100.0 / total as f64
+}
+
+/// YIQ color distance between two pixels, blending alpha against white
+fn color_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    let blend = |p: &Rgba<u8>| {
+        let alpha = p[3] as f64 / 255.0;
+        let [red, green, blue] = [p[0], p[1], p[2]].map(|c| 255.0 + (c as f64 - 255.0) * alpha);
+        (red, green, blue)
+    };
+    let (r1, g1, b1) = blend(a);
+    let (r2, g2, b2) = blend(b);
+    let (dr, dg, db) = (r1 - r2, g1 - g2, b1 - b2);
+
+    let y = dr * 0.29889531 + dg * 0.58662247 + db * 0.11448223;
+    let i = dr * 0.59597799 - dg * 0.27417610 - db * 0.32180189;
+    let q = dr * 0.21147017 - dg * 0.52261711 + db * 0.31114694;
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+/// Whether a pixel of `source` has a matching pixel within one pixel in `other`
+///
+/// Anti-aliased edges shift by a pixel between renders, so such a pixel is
+/// tolerated when both images find a match in each other's neighborhood.
+fn has_matching_neighbor(
+    source: &RgbaImage,
+    other: &RgbaImage,
+    x: u32,
+    y: u32,
+    max_delta: f64,
+) -> bool {
+    let pixel = source.get_pixel(x, y);
+    for dy in -1i64..=1 {
+        for dx in -1i64..=1 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+            if nx < 0 || ny < 0 {
+                continue;
+            }
+            if let Some(neighbor) = other.get_pixel_checked(nx as u32, ny as u32) {
+                if color_delta(pixel, neighbor) <= max_delta {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Faded grayscale copy of an unchanged pixel for the diff image
+fn faded(p: &Rgba<u8>) -> Rgba<u8> {
+    let luma = 0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64;
+    let value = (255.0 - (255.0 - luma) * 0.1 * p[3] as f64 / 255.0) as u8;
+    Rgba([value, value, value, 255])
+}
@@ -1 +1,1299 @@
-//! Input commands (click, fill, press_key)
+//! Input commands (click, fill, form controls, mouse and keyboard)
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use std::path::{Path, PathBuf};
+
+/// Largest total size of the files `set_input_files` sends into the page;
+/// they travel base64-encoded inside a single eval script
+const MAX_INPUT_FILES_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Options for actions on an element
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ActionOptions {
+    /// Skip the actionability checks and act right away
+    pub force: bool,
+    /// How long to wait for the element to become actionable
+    pub timeout_ms: u64,
+}
+
+impl Default for ActionOptions {
+    fn default() -> Self {
+        Self {
+            force: false,
+            timeout_ms: 5_000,
+        }
+    }
+}
+
+impl ActionOptions {
+    /// Read action options from request params
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        serde_json::from_value(params.clone()).map_err(|e| format!("Invalid action options: {}", e))
+    }
+}
+
+/// Mouse buttons for pointer commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseButton {
+    #[default]
+    Left,
+    Middle,
+    Right,
+}
+
+impl MouseButton {
+    fn name(self) -> &'static str {
+        match self {
+            MouseButton::Left => "left",
+            MouseButton::Middle => "middle",
+            MouseButton::Right => "right",
+        }
+    }
+}
+
+/// A local file for `set_input_files`, read so the page can build a `File`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InputFile {
+    pub name: String,
+    pub mime_type: &'static str,
+    /// Modification time in milliseconds since the Unix epoch
+    pub last_modified: u64,
+    /// Base64 file contents
+    pub data: String,
+}
+
+impl InputFile {
+    fn read(path: &Path) -> Result<Self, String> {
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let last_modified = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_millis() as u64);
+        Ok(Self {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            mime_type: mime_type_for(path),
+            last_modified,
+            data: BASE64.encode(bytes),
+        })
+    }
+}
+
+/// Read local files for `set_input_files`
+///
+/// Relative paths are resolved against `project_root`, not the app's working
+/// directory.
+pub fn read_input_files(project_root: &Path, paths: &[String]) -> Result<Vec<InputFile>, String> {
+    let paths: Vec<PathBuf> = paths.iter().map(|path| project_root.join(path)).collect();
+    let mut total = 0;
+    for path in &paths {
+        total += std::fs::metadata(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+            .len();
+    }
+    if total > MAX_INPUT_FILES_BYTES {
+        return Err(format!(
+            "Files total {} bytes, more than the {} byte limit",
+            total, MAX_INPUT_FILES_BYTES
+        ));
+    }
+    paths.iter().map(|path| InputFile::read(path)).collect()
+}
+
+/// MIME type for a file name, by extension
+fn mime_type_for(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        "txt" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "md" => "text/markdown",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Checks an element must pass before an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Checks {
+    /// Visible, stable, enabled and receiving pointer events at its center
+    Pointer,
+    /// Visible, enabled and editable
+    Editable,
+    /// Visible and enabled, for focusing before keyboard input
+    Focus,
+    /// Attached only, for controls apps often hide (file inputs)
+    Attached,
+}
+
+impl Checks {
+    fn name(self) -> &'static str {
+        match self {
+            Checks::Pointer => "pointer",
+            Checks::Editable => "editable",
+            Checks::Focus => "focus",
+            Checks::Attached => "attached",
+        }
+    }
+}
+
+/// Defines `waitForActionable(target, checks, options)`, which resolves a
+/// `{ ref, selector }` target to `{ el }` once it passes `checks` (a
+/// [`Checks`] name)
+///
+/// Selectors are locators and must match a single element. Retries until
+/// `options.timeout_ms`, then resolves to `{ error }` naming the last failed
+/// check, and the element in the way when the element would not receive the
+/// click. Expects [`super::REQUIRE_LOCATOR_JS`] before it.
+const ACTIONABILITY_JS: &str = r#"
+const label = (node) => {
+    let name = node.tagName.toLowerCase();
+    if (node.id) name += '#' + node.id;
+    const classes = Array.from(node.classList).slice(0, 2);
+    if (classes.length) name += '.' + classes.join('.');
+    const text = (node.innerText || node.textContent || '').trim().replace(/\s+/g, ' ').slice(0, 40);
+    return text ? '<' + name + '> "' + text + '"' : '<' + name + '>';
+};
+
+const scrollIntoViewIfNeeded = (node) => {
+    const r = node.getBoundingClientRect();
+    if (r.top < 0 || r.left < 0 || r.bottom > window.innerHeight || r.right > window.innerWidth) {
+        node.scrollIntoView({ behavior: 'instant', block: 'center', inline: 'center' });
+    }
+};
+
+const nextFrame = () => new Promise((resolve) => requestAnimationFrame(() => resolve()));
+
+// Deepest element at a viewport point, looking into open shadow roots
+const hitAt = (x, y) => {
+    let hit = document.elementFromPoint(x, y);
+    while (hit && hit.shadowRoot) {
+        const inner = hit.shadowRoot.elementFromPoint(x, y);
+        if (!inner || inner === hit) break;
+        hit = inner;
+    }
+    return hit;
+};
+
+const isInside = (node, container) => {
+    for (let n = node; n; n = n.parentNode || n.host) {
+        if (n === container) return true;
+    }
+    return false;
+};
+
+// Returns { el }, { reason } to retry, or { fatal } to give up
+const findTarget = (target) => {
+    if (target.ref !== null) {
+        const found = window.__MCP_LOCATOR__.resolveRef(target.ref);
+        return found.error ? { fatal: found.error } : { el: found.el };
+    }
+    let found;
+    try {
+        found = window.__MCP_LOCATOR__.strict(target.selector);
+    } catch (e) {
+        return { fatal: e.message };
+    }
+    if (found.error) {
+        return { fatal: found.error };
+    }
+    return found.el ? { el: found.el } : { reason: 'Element not found: ' + target.selector };
+};
+
+// Returns null if the element is actionable, otherwise why not
+const checkActionable = async (node, targetName, checks) => {
+    const prefix = 'Element ' + targetName + ' ' + label(node);
+    if (checks === 'attached') {
+        return null;
+    }
+
+    for (let ancestor = node; ancestor; ancestor = ancestor.parentElement) {
+        if (window.getComputedStyle(ancestor).display === 'none') {
+            return ancestor === node
+                ? prefix + ' is not visible (display: none)'
+                : prefix + ' is not visible: hidden by ' + label(ancestor) + ' (display: none)';
+        }
+    }
+    const visibility = window.getComputedStyle(node).visibility;
+    if (visibility !== 'visible') {
+        return prefix + ' is not visible (visibility: ' + visibility + ')';
+    }
+    const box = node.getBoundingClientRect();
+    if (box.width === 0 || box.height === 0) {
+        return prefix + ' is not visible (zero size)';
+    }
+
+    if (node.matches(':disabled')) {
+        const fieldset = node.closest('fieldset:disabled');
+        return fieldset && !node.disabled
+            ? prefix + ' is disabled by ' + label(fieldset)
+            : prefix + ' is disabled';
+    }
+    const ariaDisabled = node.closest('[aria-disabled="true"]');
+    if (ariaDisabled) {
+        return ariaDisabled === node
+            ? prefix + ' is disabled (aria-disabled)'
+            : prefix + ' is disabled by ' + label(ariaDisabled) + ' (aria-disabled)';
+    }
+
+    if (checks === 'focus') {
+        return null;
+    }
+    if (checks === 'editable') {
+        const tag = node.tagName.toLowerCase();
+        if (node.isContentEditable) {
+            return null;
+        }
+        if (tag !== 'input' && tag !== 'textarea') {
+            return prefix + ' is not an <input>, <textarea> or [contenteditable] element';
+        }
+        if (tag === 'input' && ['checkbox', 'radio', 'file', 'button', 'submit', 'reset', 'image'].includes(node.type)) {
+            return prefix + ' is an <input type="' + node.type + '"> and cannot be filled';
+        }
+        if (node.readOnly || node.getAttribute('aria-readonly') === 'true') {
+            return prefix + ' is read-only';
+        }
+        return null;
+    }
+
+    scrollIntoViewIfNeeded(node);
+
+    // Stable: the same box on two consecutive animation frames
+    await nextFrame();
+    const before = node.getBoundingClientRect();
+    await nextFrame();
+    const after = node.getBoundingClientRect();
+    if (before.x !== after.x || before.y !== after.y || before.width !== after.width || before.height !== after.height) {
+        return prefix + ' is not stable (moving or animating)';
+    }
+
+    // Receives events: the hit target at its center is the element or inside it
+    const x = Math.min(Math.max(after.left + after.width / 2, 0), window.innerWidth - 1);
+    const y = Math.min(Math.max(after.top + after.height / 2, 0), window.innerHeight - 1);
+    const hit = hitAt(x, y);
+    if (!hit) {
+        return prefix + ' is outside the viewport';
+    }
+    if (isInside(hit, node)) {
+        return null;
+    }
+    const hitLabel = hit.closest('label');
+    if (hitLabel && hitLabel.control === node) {
+        return null;
+    }
+    return prefix + ' does not receive pointer events: ' + label(hit) + ' would receive the click';
+};
+
+const waitForActionable = async (target, checks, options) => {
+    const targetName = target.ref !== null ? 'ref=' + target.ref : target.selector;
+    const deadline = Date.now() + options.timeout_ms;
+    while (true) {
+        const found = findTarget(target);
+        if (found.fatal) {
+            return { error: found.fatal };
+        }
+        let reason = found.reason;
+        if (found.el) {
+            reason = options.force ? null : await checkActionable(found.el, targetName, checks);
+            if (reason === null) {
+                scrollIntoViewIfNeeded(found.el);
+                return { el: found.el };
+            }
+        }
+        if (Date.now() >= deadline) {
+            return { error: reason + ' (waited ' + options.timeout_ms + 'ms)' };
+        }
+        await new Promise((resolve) => setTimeout(resolve, 50));
+    }
+};
+"#;
+
+/// Defines the mouse helpers `moveTo`, `press` and `release`, which dispatch
+/// the pointer and mouse events of a real mouse at viewport points
+///
+/// The pointer position, pressed buttons and hovered element persist in
+/// `window.__MCP_MOUSE__` between commands. Expects [`ACTIONABILITY_JS`].
+const POINTER_JS: &str = r#"
+const mouse = window.__MCP_MOUSE__ = window.__MCP_MOUSE__ || { x: 0, y: 0, buttons: 0, over: null, down: null };
+const BUTTON = { left: 0, middle: 1, right: 2 };
+const BUTTON_BIT = { left: 1, middle: 4, right: 2 };
+
+const fire = (target, type, x, y, init) => {
+    const base = {
+        bubbles: true,
+        cancelable: true,
+        composed: true,
+        view: window,
+        clientX: x,
+        clientY: y,
+        screenX: window.screenX + x,
+        screenY: window.screenY + y,
+        buttons: mouse.buttons,
+        ...init,
+    };
+    const event = type.startsWith('pointer')
+        ? new PointerEvent(type, { pointerId: 1, pointerType: 'mouse', isPrimary: true, width: 1, height: 1, pressure: mouse.buttons ? 0.5 : 0, ...base })
+        : new MouseEvent(type, base);
+    return target.dispatchEvent(event);
+};
+
+const fireDrag = (target, type, x, y, dataTransfer) => {
+    const init = { bubbles: true, cancelable: true, composed: true, view: window, clientX: x, clientY: y, buttons: mouse.buttons };
+    let event = null;
+    try {
+        event = new DragEvent(type, { ...init, dataTransfer: dataTransfer });
+    } catch (e) {
+        event = null;
+    }
+    // Some engines ignore a constructed DataTransfer, so attach it by hand
+    if (!event || event.dataTransfer !== dataTransfer) {
+        event = new MouseEvent(type, init);
+        Object.defineProperty(event, 'dataTransfer', { value: dataTransfer });
+    }
+    return target.dispatchEvent(event);
+};
+
+// The element `el` receives at a point: the hit element inside it, or itself
+const aimAt = (el, x, y) => {
+    const hit = hitAt(x, y);
+    return hit && isInside(hit, el) ? hit : el;
+};
+
+const center = (el) => {
+    const r = el.getBoundingClientRect();
+    return { x: r.left + r.width / 2, y: r.top + r.height / 2 };
+};
+
+const moveTo = (x, y, target) => {
+    target = target || hitAt(x, y);
+    if (target !== mouse.over) {
+        const previous = mouse.over && mouse.over.isConnected ? mouse.over : null;
+        if (previous) {
+            fire(previous, 'pointerout', x, y, { relatedTarget: target });
+            fire(previous, 'pointerleave', x, y, { bubbles: false, relatedTarget: target });
+            fire(previous, 'mouseout', x, y, { relatedTarget: target });
+            fire(previous, 'mouseleave', x, y, { bubbles: false, relatedTarget: target });
+        }
+        if (target) {
+            fire(target, 'pointerover', x, y, { relatedTarget: previous });
+            fire(target, 'pointerenter', x, y, { bubbles: false, relatedTarget: previous });
+            fire(target, 'mouseover', x, y, { relatedTarget: previous });
+            fire(target, 'mouseenter', x, y, { bubbles: false, relatedTarget: previous });
+        }
+        mouse.over = target;
+    }
+    mouse.x = x;
+    mouse.y = y;
+    if (target) {
+        fire(target, 'pointermove', x, y);
+        fire(target, 'mousemove', x, y);
+    }
+    return target;
+};
+
+const press = (x, y, button, target, detail) => {
+    target = moveTo(x, y, target);
+    if (!target) return null;
+    mouse.buttons |= BUTTON_BIT[button];
+    mouse.down = target;
+    const init = { button: BUTTON[button], detail: detail };
+    fire(target, 'pointerdown', x, y, init);
+    if (fire(target, 'mousedown', x, y, init) && button === 'left') {
+        const focusable = target.closest('input, textarea, select, button, a[href], [tabindex], [contenteditable]');
+        if (focusable) {
+            focusable.focus({ preventScroll: true });
+        }
+    }
+    if (button === 'right') {
+        fire(target, 'contextmenu', x, y, init);
+    }
+    return target;
+};
+
+const release = (x, y, button, target, detail) => {
+    target = target || hitAt(x, y);
+    if (!target) return null;
+    mouse.buttons &= ~BUTTON_BIT[button];
+    const init = { button: BUTTON[button], detail: detail };
+    fire(target, 'pointerup', x, y, init);
+    fire(target, 'mouseup', x, y, init);
+    // The click goes to the nearest common ancestor of the press and release targets
+    let common = mouse.down ? target : null;
+    while (common && !isInside(mouse.down, common)) {
+        common = common.parentElement;
+    }
+    if (common) {
+        fire(common, button === 'left' ? 'click' : 'auxclick', x, y, init);
+    }
+    mouse.down = null;
+    return target;
+};
+"#;
+
+/// JSON `{ ref, selector }` target for `waitForActionable`
+fn target_json(ref_num: Option<u32>, selector: Option<&str>) -> String {
+    serde_json::json!({ "ref": ref_num, "selector": selector }).to_string()
+}
+
+/// JavaScript snippet resolving `el` from a snapshot ref or locator once
+/// it is actionable, unless `options.force` is set
+/// Returns an error result from the enclosing script otherwise
+fn actionable_lookup_js(
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    checks: Checks,
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{require_locator}
+{actionability}
+const options = {options};
+const found = await waitForActionable({target}, {checks}, options);
+if (found.error) {{
+    return {{ success: false, error: found.error }};
+}}
+const el = found.el;
+"#,
+        require_locator = super::REQUIRE_LOCATOR_JS,
+        actionability = ACTIONABILITY_JS,
+        options = serde_json::to_string(options).unwrap(),
+        target = target_json(ref_num, selector),
+        checks = serde_json::to_string(checks.name()).unwrap(),
+    )
+}
+
+/// JavaScript code to click an element by snapshot ref or locator
+/// Waits for the element to be visible, stable, enabled and hit by a click at
+/// its center unless `options.force` is set
+pub fn click_js(ref_num: Option<u32>, selector: Option<&str>, options: &ActionOptions) -> String {
+    format!(
+        r#"
+{lookup}
+el.click();
+return {{ success: true }};
+"#,
+        lookup = actionable_lookup_js(ref_num, selector, Checks::Pointer, options)
+    )
+}
+
+/// JavaScript code to move the mouse onto, double-click or right-click an
+/// element by snapshot ref or locator
+/// `action` is `hover`, `dblclick` or `right_click`
+pub fn pointer_action_js(
+    action: &str,
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{lookup}
+{pointer}
+const action = {action};
+const point = center(el);
+const target = aimAt(el, point.x, point.y);
+if (action === 'hover') {{
+    moveTo(point.x, point.y, target);
+}} else if (action === 'dblclick') {{
+    for (const detail of [1, 2]) {{
+        press(point.x, point.y, 'left', target, detail);
+        release(point.x, point.y, 'left', target, detail);
+    }}
+    fire(target, 'dblclick', point.x, point.y, {{ button: 0, detail: 2 }});
+}} else {{
+    press(point.x, point.y, 'right', target, 1);
+    release(point.x, point.y, 'right', target, 1);
+}}
+return {{ success: true, x: point.x, y: point.y }};
+"#,
+        lookup = actionable_lookup_js(ref_num, selector, Checks::Pointer, options),
+        pointer = POINTER_JS,
+        action = serde_json::to_string(action).unwrap()
+    )
+}
+
+/// JavaScript code to move, press or release the mouse at a viewport point
+/// (CSS pixels)
+/// `action` is `move`, `down` or `up`. Pressed buttons carry over to later
+/// moves, so `down`, `move`, `up` drags whatever listens to pointer events
+pub fn mouse_js(action: &str, x: f64, y: f64, button: MouseButton) -> String {
+    format!(
+        r#"
+{lookup}
+{require_locator}
+{actionability}
+{pointer}
+const action = {action};
+const button = {button};
+let target;
+if (action === 'move') {{
+    target = moveTo(x, y);
+}} else if (action === 'down') {{
+    target = press(x, y, button, null, 1);
+}} else {{
+    target = release(x, y, button, null, 1);
+}}
+return {{ success: true, x: x, y: y, buttons: mouse.buttons, element: describe(target) }};
+"#,
+        lookup = super::point_lookup_js(x, y),
+        require_locator = super::REQUIRE_LOCATOR_JS,
+        actionability = ACTIONABILITY_JS,
+        pointer = POINTER_JS,
+        action = serde_json::to_string(action).unwrap(),
+        button = serde_json::to_string(button.name()).unwrap()
+    )
+}
+
+/// JavaScript code to drag one element onto another, each by snapshot ref or
+/// locator
+///
+/// Moves the pressed mouse in `steps` animation frames. Draggable sources
+/// (`draggable`, links, images) get the HTML drag-and-drop sequence with a
+/// shared `DataTransfer`; anything else gets pointer and mouse moves for
+/// pointer-driven libraries.
+pub fn drag_js(
+    from: (Option<u32>, Option<&str>),
+    to: (Option<u32>, Option<&str>),
+    steps: u32,
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{require_locator}
+{actionability}
+{pointer}
+const options = {options};
+const steps = {steps};
+
+const from = await waitForActionable({from}, 'pointer', options);
+if (from.error) {{
+    return {{ success: false, error: 'Drag source: ' + from.error }};
+}}
+const to = await waitForActionable({to}, 'pointer', options);
+if (to.error) {{
+    return {{ success: false, error: 'Drop target: ' + to.error }};
+}}
+// Scrolling to the drop target may have moved the source
+scrollIntoViewIfNeeded(from.el);
+const start = center(from.el);
+const end = center(to.el);
+
+let draggable = null;
+for (let n = from.el; n; n = n.parentElement) {{
+    if (n.draggable) {{
+        draggable = n;
+        break;
+    }}
+}}
+
+press(start.x, start.y, 'left', aimAt(from.el, start.x, start.y), 1);
+
+let dataTransfer = null;
+if (draggable) {{
+    dataTransfer = new DataTransfer();
+    if (fireDrag(draggable, 'dragstart', start.x, start.y, dataTransfer)) {{
+        fire(mouse.down || draggable, 'pointercancel', start.x, start.y);
+    }} else {{
+        // Cancelled dragstart: no drag, fall back to plain moves
+        dataTransfer = null;
+    }}
+}}
+
+let over = null;
+let dropAllowed = false;
+for (let i = 1; i <= steps; i++) {{
+    const x = start.x + (end.x - start.x) * i / steps;
+    const y = start.y + (end.y - start.y) * i / steps;
+    if (dataTransfer) {{
+        const hit = i === steps ? aimAt(to.el, x, y) : hitAt(x, y);
+        fireDrag(draggable, 'drag', x, y, dataTransfer);
+        if (hit !== over) {{
+            if (over) fireDrag(over, 'dragleave', x, y, dataTransfer);
+            if (hit) fireDrag(hit, 'dragenter', x, y, dataTransfer);
+            over = hit;
+        }}
+        // Cancelling dragover is how a drop target accepts the drop
+        dropAllowed = over ? !fireDrag(over, 'dragover', x, y, dataTransfer) : false;
+        mouse.x = x;
+        mouse.y = y;
+    }} else {{
+        moveTo(x, y, i === steps ? aimAt(to.el, x, y) : null);
+    }}
+    await nextFrame();
+}}
+
+if (dataTransfer) {{
+    if (over && dropAllowed) {{
+        fireDrag(over, 'drop', end.x, end.y, dataTransfer);
+    }} else if (over) {{
+        fireDrag(over, 'dragleave', end.x, end.y, dataTransfer);
+    }}
+    mouse.buttons = 0;
+    fireDrag(draggable, 'dragend', end.x, end.y, dataTransfer);
+    mouse.down = null;
+}} else {{
+    release(end.x, end.y, 'left', aimAt(to.el, end.x, end.y), 1);
+}}
+return {{
+    success: true,
+    mode: dataTransfer ? 'html5' : 'pointer',
+    dropped: dataTransfer ? dropAllowed : undefined,
+    from: start,
+    to: end,
+}};
+"#,
+        require_locator = super::REQUIRE_LOCATOR_JS,
+        actionability = ACTIONABILITY_JS,
+        pointer = POINTER_JS,
+        options = serde_json::to_string(options).unwrap(),
+        steps = steps.max(1),
+        from = target_json(from.0, from.1),
+        to = target_json(to.0, to.1),
+    )
+}
+
+/// JavaScript code to fill an input, textarea or `contenteditable` editor by
+/// snapshot ref or locator
+/// Waits for the element to be visible, enabled and editable unless
+/// `options.force` is set. Uses native value setter to properly trigger
+/// React's synthetic event system. Date, time and color inputs must be given
+/// values in their wire format (`2024-01-31`, `#ff0000`); range inputs report
+/// the clamped value
+pub fn fill_js(
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    value: &str,
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{lookup}
+const value = {value};
+el.focus();
+
+// Rich text editors: replace the content through editing commands, which
+// fire beforeinput/input like real typing
+if (el.isContentEditable) {{
+    const selection = window.getSelection();
+    selection.selectAllChildren(el);
+    const edited = value
+        ? document.execCommand('insertText', false, value)
+        : document.execCommand('delete', false);
+    if (!edited) {{
+        el.textContent = value;
+        el.dispatchEvent(new InputEvent('input', {{ inputType: 'insertText', data: value, bubbles: true, composed: true }}));
+    }}
+    return {{ success: true }};
+}}
+
+// Get the native value setter to bypass React's synthetic event system
+// This is required for React controlled inputs to properly update state
+const tagName = el.tagName.toLowerCase();
+const prototype = tagName === 'textarea' ? window.HTMLTextAreaElement.prototype : window.HTMLInputElement.prototype;
+const nativeValueSetter = Object.getOwnPropertyDescriptor(prototype, 'value')?.set;
+const setValue = (v) => (nativeValueSetter ? nativeValueSetter.call(el, v) : (el.value = v));
+const previous = el.value;
+setValue(value);
+
+// Date, time, color and range inputs sanitize values they cannot parse
+const type = tagName === 'input' ? el.type : '';
+if (['date', 'time', 'datetime-local', 'month', 'week', 'color'].includes(type) && value
+    && el.value.toLowerCase() !== value.toLowerCase()) {{
+    const normalized = el.value;
+    setValue(previous);
+    return {{ success: false, error: 'Malformed value "' + value + '" for <input type="' + type + '">' + (normalized ? ' (normalized to "' + normalized + '")' : '') }};
+}}
+if (type === 'range' && (value === '' || isNaN(Number(value)))) {{
+    setValue(previous);
+    return {{ success: false, error: 'Value "' + value + '" for <input type="range"> is not a number' }};
+}}
+
+// Dispatch input event with bubbles to trigger React's onChange
+const inputEvent = new Event('input', {{ bubbles: true, cancelable: true }});
+// React 16+ uses this property to track the event
+Object.defineProperty(inputEvent, 'simulated', {{ value: true }});
+el.dispatchEvent(inputEvent);
+
+// Also dispatch change event for completeness
+el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+
+return {{ success: true, value: el.value }};
+"#,
+        lookup = actionable_lookup_js(ref_num, selector, Checks::Editable, options),
+        value = serde_json::to_string(value).unwrap()
+    )
+}
+
+/// JavaScript code to select options of a `<select>` by snapshot ref or
+/// locator
+/// `values` is an array whose items are a string (matched against option
+/// values, then labels) or `{ value, label, index }`. Waits for matching
+/// options to appear; an empty array deselects everything
+pub fn select_option_js(
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    values: &serde_json::Value,
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{lookup}
+const wanted = {values};
+if (!(el instanceof HTMLSelectElement)) {{
+    return {{ success: false, error: 'Element ' + label(el) + ' is not a <select>' }};
+}}
+if (!el.multiple && wanted.length > 1) {{
+    return {{ success: false, error: 'Element ' + label(el) + ' is not a multi-select; pass a single option' }};
+}}
+
+const optionText = (option) => option.label || option.text.trim();
+const matches = (option, index, want) => typeof want === 'string'
+    ? option.value === want || optionText(option) === want
+    : (want.value === undefined || option.value === want.value)
+        && (want.label === undefined || optionText(option) === want.label)
+        && (want.index === undefined || index === want.index);
+
+// Options are often loaded asynchronously, so wait for every match
+const deadline = Date.now() + options.timeout_ms;
+let chosen;
+while (true) {{
+    const all = Array.from(el.options);
+    chosen = [];
+    let missing = null;
+    for (const want of wanted) {{
+        // Exact value matches win over label matches
+        const option = (typeof want === 'string' && all.find((o) => o.value === want))
+            || all.find((o, i) => matches(o, i, want));
+        if (!option) {{
+            missing = want;
+            break;
+        }}
+        chosen.push(option);
+    }}
+    if (missing === null) break;
+    if (Date.now() >= deadline) {{
+        const available = all.slice(0, 20).map((o) => JSON.stringify(optionText(o))).join(', ');
+        return {{ success: false, error: 'No option matching ' + JSON.stringify(missing) + ' in ' + label(el) + ' (options: ' + (available || 'none') + ')' }};
+    }}
+    await new Promise((resolve) => setTimeout(resolve, 50));
+}}
+const disabled = chosen.find((o) => o.disabled);
+if (disabled) {{
+    return {{ success: false, error: 'Option ' + JSON.stringify(optionText(disabled)) + ' is disabled' }};
+}}
+
+el.focus();
+for (const option of el.options) {{
+    option.selected = chosen.includes(option);
+}}
+el.dispatchEvent(new Event('input', {{ bubbles: true, composed: true }}));
+el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+return {{ success: true, values: chosen.map((o) => o.value) }};
+"#,
+        lookup = actionable_lookup_js(ref_num, selector, Checks::Focus, options),
+        values = values
+    )
+}
+
+/// JavaScript code to check or uncheck a checkbox, radio button or ARIA
+/// checkbox/radio/switch by snapshot ref or locator
+/// Clicks the control (or its label) only if its state differs, then verifies
+/// the state changed
+pub fn set_checked_js(
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    checked: bool,
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{lookup}
+const wantChecked = {checked};
+const control = el instanceof HTMLLabelElement && el.control ? el.control : el;
+const native = control instanceof HTMLInputElement && (control.type === 'checkbox' || control.type === 'radio');
+const role = control.getAttribute('role');
+if (!native && !['checkbox', 'radio', 'switch', 'menuitemcheckbox', 'menuitemradio'].includes(role)) {{
+    return {{ success: false, error: 'Element ' + label(el) + ' is not a checkbox, radio button or switch' }};
+}}
+const isChecked = () => (native ? control.checked : control.getAttribute('aria-checked') === 'true');
+if (isChecked() === wantChecked) {{
+    return {{ success: true, checked: wantChecked, changed: false }};
+}}
+const radio = native ? control.type === 'radio' : role === 'radio' || role === 'menuitemradio';
+if (radio && !wantChecked) {{
+    return {{ success: false, error: 'Cannot uncheck radio button ' + label(control) + '; check another option in its group' }};
+}}
+
+el.click();
+await nextFrame();
+if (isChecked() !== wantChecked) {{
+    return {{ success: false, error: 'Clicking ' + label(el) + ' did not ' + (wantChecked ? 'check' : 'uncheck') + ' it' }};
+}}
+return {{ success: true, checked: wantChecked, changed: true }};
+"#,
+        lookup = actionable_lookup_js(ref_num, selector, Checks::Focus, options),
+        checked = checked
+    )
+}
+
+/// JavaScript code to set the files of an `<input type="file">` (or its
+/// label) by snapshot ref or locator
+/// Builds `File`s from the given contents in a `DataTransfer`, then fires
+/// `input` and `change`. No files clears the input. Hidden inputs are fine
+pub fn set_input_files_js(
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    files: &[InputFile],
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{lookup}
+const files = {files};
+const input = el instanceof HTMLLabelElement && el.control ? el.control : el;
+if (!(input instanceof HTMLInputElement) || input.type !== 'file') {{
+    return {{ success: false, error: 'Element ' + label(el) + ' is not an <input type="file">' }};
+}}
+if (input.disabled) {{
+    return {{ success: false, error: 'Element ' + label(input) + ' is disabled' }};
+}}
+if (files.length > 1 && !input.multiple) {{
+    return {{ success: false, error: 'Element ' + label(input) + ' accepts a single file' }};
+}}
+
+const transfer = new DataTransfer();
+for (const file of files) {{
+    const binary = atob(file.data);
+    const bytes = new Uint8Array(binary.length);
+    for (let i = 0; i < binary.length; i++) {{
+        bytes[i] = binary.charCodeAt(i);
+    }}
+    transfer.items.add(new File([bytes], file.name, {{ type: file.mime_type, lastModified: file.last_modified }}));
+}}
+input.files = transfer.files;
+input.dispatchEvent(new Event('input', {{ bubbles: true, composed: true }}));
+input.dispatchEvent(new Event('change', {{ bubbles: true }}));
+return {{
+    success: true,
+    files: Array.from(input.files).map((f) => ({{ name: f.name, size: f.size, type: f.type }})),
+}};
+"#,
+        lookup = actionable_lookup_js(ref_num, selector, Checks::Attached, options),
+        files = serde_json::to_string(files).unwrap()
+    )
+}
+
+/// Defines `keyDefinition(name)`, `keyDown(def)` and `keyUp(def)`, which
+/// dispatch keyboard events to the focused element and emulate the browser's
+/// default actions
+///
+/// Key definitions follow a US layout. Default actions: text insertion with
+/// `beforeinput`/`input`, Backspace/Delete editing, caret keys, Tab focus
+/// traversal, Enter line breaks and implicit form submission, and Enter/Space
+/// activation of buttons.
+const KEYBOARD_JS: &str = r#"
+const KEYS = {};
+const defineKey = (key, code, keyCode, extra) => {
+    KEYS[key] = { key: key, code: code, keyCode: keyCode, location: 0, ...extra };
+};
+defineKey('Shift', 'ShiftLeft', 16, { location: 1 });
+defineKey('Control', 'ControlLeft', 17, { location: 1 });
+defineKey('Alt', 'AltLeft', 18, { location: 1 });
+defineKey('Meta', 'MetaLeft', 91, { location: 1 });
+defineKey('Enter', 'Enter', 13, { text: '\r' });
+defineKey('Tab', 'Tab', 9);
+defineKey('Backspace', 'Backspace', 8);
+defineKey('Delete', 'Delete', 46);
+defineKey('Escape', 'Escape', 27);
+defineKey(' ', 'Space', 32, { text: ' ' });
+defineKey('ArrowLeft', 'ArrowLeft', 37);
+defineKey('ArrowUp', 'ArrowUp', 38);
+defineKey('ArrowRight', 'ArrowRight', 39);
+defineKey('ArrowDown', 'ArrowDown', 40);
+defineKey('Home', 'Home', 36);
+defineKey('End', 'End', 35);
+defineKey('PageUp', 'PageUp', 33);
+defineKey('PageDown', 'PageDown', 34);
+defineKey('Insert', 'Insert', 45);
+defineKey('CapsLock', 'CapsLock', 20);
+defineKey('ContextMenu', 'ContextMenu', 93);
+for (let i = 1; i <= 12; i++) {
+    defineKey('F' + i, 'F' + i, 111 + i);
+}
+for (let i = 0; i < 26; i++) {
+    const lower = String.fromCharCode(97 + i);
+    const upper = lower.toUpperCase();
+    defineKey(lower, 'Key' + upper, 65 + i, { text: lower, shifted: upper });
+    defineKey(upper, 'Key' + upper, 65 + i, { text: upper, shift: true });
+}
+const SHIFTED_DIGITS = ')!@#$%^&*(';
+for (let i = 0; i < 10; i++) {
+    defineKey(String(i), 'Digit' + i, 48 + i, { text: String(i), shifted: SHIFTED_DIGITS[i] });
+    defineKey(SHIFTED_DIGITS[i], 'Digit' + i, 48 + i, { text: SHIFTED_DIGITS[i], shift: true });
+}
+for (const [key, shifted, code, keyCode] of [
+    ['-', '_', 'Minus', 189], ['=', '+', 'Equal', 187], ['[', '{', 'BracketLeft', 219],
+    [']', '}', 'BracketRight', 221], ['\\', '|', 'Backslash', 220], [';', ':', 'Semicolon', 186],
+    ["'", '"', 'Quote', 222], [',', '<', 'Comma', 188], ['.', '>', 'Period', 190],
+    ['/', '?', 'Slash', 191], ['`', '~', 'Backquote', 192],
+]) {
+    defineKey(key, code, keyCode, { text: key, shifted: shifted });
+    defineKey(shifted, code, keyCode, { text: shifted, shift: true });
+}
+
+const isMac = /Mac|iPhone|iPad/.test(navigator.platform || navigator.userAgent);
+const ALIASES = {
+    ctrl: 'Control', control: 'Control', cmd: 'Meta', command: 'Meta', meta: 'Meta',
+    option: 'Alt', alt: 'Alt', shift: 'Shift', esc: 'Escape', return: 'Enter', del: 'Delete',
+    space: ' ', up: 'ArrowUp', down: 'ArrowDown', left: 'ArrowLeft', right: 'ArrowRight',
+    controlormeta: isMac ? 'Meta' : 'Control',
+};
+
+// Definition for a key name, an alias or any single character; null if unknown
+const keyDefinition = (name) => {
+    if (KEYS[name]) return KEYS[name];
+    const lower = name.toLowerCase();
+    if (ALIASES[lower]) return KEYS[ALIASES[lower]];
+    const named = Object.keys(KEYS).find((key) => key.length > 1 && key.toLowerCase() === lower);
+    if (named) return KEYS[named];
+    if ([...name].length === 1) return { key: name, code: '', keyCode: 0, location: 0, text: name };
+    return null;
+};
+
+const modifiers = { Shift: false, Control: false, Alt: false, Meta: false };
+const dirtyFields = window.__MCP_DIRTY_FIELDS__ = window.__MCP_DIRTY_FIELDS__ || new WeakSet();
+const TEXT_INPUT_TYPES = ['text', 'search', 'url', 'tel', 'email', 'password', 'number'];
+
+const focusedElement = () => {
+    let active = document.activeElement;
+    while (active && active.shadowRoot && active.shadowRoot.activeElement) {
+        active = active.shadowRoot.activeElement;
+    }
+    return active || document.body;
+};
+
+const isTextField = (node) => node instanceof HTMLTextAreaElement
+    || (node instanceof HTMLInputElement && TEXT_INPUT_TYPES.includes(node.type));
+
+const keyEvent = (target, type, def) => {
+    const code = type === 'keypress' ? def.text.charCodeAt(0) : def.keyCode;
+    const event = new KeyboardEvent(type, {
+        key: def.key,
+        code: def.code,
+        location: def.location,
+        bubbles: true,
+        cancelable: true,
+        composed: true,
+        view: window,
+        shiftKey: modifiers.Shift,
+        ctrlKey: modifiers.Control,
+        altKey: modifiers.Alt,
+        metaKey: modifiers.Meta,
+    });
+    // Legacy fields cannot be set through the constructor
+    Object.defineProperty(event, 'keyCode', { get: () => code });
+    Object.defineProperty(event, 'which', { get: () => code });
+    Object.defineProperty(event, 'charCode', { get: () => (type === 'keypress' ? code : 0) });
+    return target.dispatchEvent(event);
+};
+
+// Native change fires when an edited field loses focus or submits
+const commitChange = (node) => {
+    if (dirtyFields.has(node)) {
+        dirtyFields.delete(node);
+        node.dispatchEvent(new Event('change', { bubbles: true }));
+    }
+};
+
+const setFieldValue = (node, value) => {
+    // Native setter so React's value tracker sees the change
+    const prototype = node instanceof HTMLTextAreaElement ? HTMLTextAreaElement.prototype : HTMLInputElement.prototype;
+    const setter = Object.getOwnPropertyDescriptor(prototype, 'value')?.set;
+    if (setter) {
+        setter.call(node, value);
+    } else {
+        node.value = value;
+    }
+};
+
+const selectionOf = (node) => {
+    try {
+        if (node.selectionStart !== null) return [node.selectionStart, node.selectionEnd];
+    } catch (e) {
+        // email and number inputs have no selection API
+    }
+    return [node.value.length, node.value.length];
+};
+
+const setCaret = (node, position) => {
+    try {
+        node.setSelectionRange(position, position);
+    } catch (e) {
+        // email and number inputs have no selection API
+    }
+};
+
+// Apply an edit the way the browser does: beforeinput, value change, input
+const editField = (target, inputType, data) => {
+    if (target.isContentEditable) {
+        // execCommand dispatches beforeinput and input itself
+        const command = {
+            insertText: 'insertText',
+            insertLineBreak: 'insertLineBreak',
+            insertParagraph: 'insertParagraph',
+            deleteContentBackward: 'delete',
+            deleteContentForward: 'forwardDelete',
+        }[inputType];
+        document.execCommand(command, false, data);
+        return;
+    }
+    if (!isTextField(target) || target.readOnly || target.disabled) return;
+
+    const value = target.value;
+    let [start, end] = selectionOf(target);
+    if (inputType === 'deleteContentBackward' && start === end) {
+        start = Math.max(0, start - 1);
+    } else if (inputType === 'deleteContentForward' && start === end) {
+        end = Math.min(value.length, end + 1);
+    }
+    const inserted = data || '';
+    if (!inserted && start === end) return;
+    if (inserted && target.maxLength >= 0 && value.length - (end - start) + inserted.length > target.maxLength) return;
+
+    const init = { inputType: inputType, data: data, bubbles: true, composed: true };
+    if (!target.dispatchEvent(new InputEvent('beforeinput', { ...init, cancelable: true }))) return;
+    setFieldValue(target, value.slice(0, start) + inserted + value.slice(end));
+    setCaret(target, start + inserted.length);
+    target.dispatchEvent(new InputEvent('input', init));
+    dirtyFields.add(target);
+};
+
+const FOCUSABLE = 'a[href], area[href], button, input:not([type="hidden"]), select, textarea, iframe, summary, '
+    + '[tabindex], [contenteditable]:not([contenteditable="false"])';
+
+// Tab order: positive tabindex ascending, then tabindex 0 in document order
+const moveFocus = (direction) => {
+    const candidates = Array.from(document.querySelectorAll(FOCUSABLE)).filter((node) => node.tabIndex >= 0
+        && !node.matches(':disabled')
+        && node.getClientRects().length > 0
+        && window.getComputedStyle(node).visibility === 'visible');
+    const ordered = [
+        ...candidates.filter((node) => node.tabIndex > 0).sort((a, b) => a.tabIndex - b.tabIndex),
+        ...candidates.filter((node) => node.tabIndex === 0),
+    ];
+    if (!ordered.length) return;
+    const current = focusedElement();
+    const index = ordered.indexOf(current);
+    const next = index === -1
+        ? ordered[direction > 0 ? 0 : ordered.length - 1]
+        : ordered[(index + direction + ordered.length) % ordered.length];
+    commitChange(current);
+    next.focus();
+    if (isTextField(next)) {
+        next.select();
+    }
+};
+
+const implicitSubmit = (input) => {
+    commitChange(input);
+    const form = input.form;
+    if (!form) return;
+    const submitter = Array.from(form.elements).find((node) => node.matches(
+        'button:not([type]), button[type="submit"], input[type="submit"], input[type="image"]'));
+    if (submitter) {
+        if (!submitter.matches(':disabled')) submitter.click();
+    } else if (typeof form.requestSubmit === 'function') {
+        form.requestSubmit();
+    } else if (form.dispatchEvent(new Event('submit', { bubbles: true, cancelable: true }))) {
+        form.submit();
+    }
+};
+
+const ACTIVATABLE = 'a[href], button, summary, input[type="button"], input[type="submit"], input[type="reset"], input[type="image"]';
+let spaceArmed = false;
+
+const keyDown = (def) => {
+    if (def.key in modifiers) {
+        modifiers[def.key] = true;
+    }
+    const target = focusedElement();
+    if (!keyEvent(target, 'keydown', def) || def.key in modifiers) return;
+
+    const shortcut = modifiers.Control || modifiers.Meta || modifiers.Alt;
+    if (def.text && !shortcut) {
+        if (!keyEvent(target, 'keypress', def)) return;
+        if (def.key !== 'Enter') {
+            spaceArmed = def.key === ' ';
+            editField(target, 'insertText', def.text);
+        } else if (target instanceof HTMLTextAreaElement) {
+            editField(target, 'insertLineBreak', '\n');
+        } else if (target.isContentEditable) {
+            editField(target, 'insertParagraph', null);
+        } else if (isTextField(target)) {
+            implicitSubmit(target);
+        } else if (target.matches(ACTIVATABLE)) {
+            target.click();
+        }
+        return;
+    }
+
+    if (def.key === 'Tab') {
+        if (!shortcut) moveFocus(modifiers.Shift ? -1 : 1);
+    } else if (def.key === 'Backspace' && !shortcut) {
+        editField(target, 'deleteContentBackward', null);
+    } else if (def.key === 'Delete' && !shortcut) {
+        editField(target, 'deleteContentForward', null);
+    } else if (isTextField(target) && ['ArrowLeft', 'ArrowRight', 'Home', 'End'].includes(def.key)) {
+        const [start, end] = selectionOf(target);
+        const position = {
+            ArrowLeft: start === end ? Math.max(0, start - 1) : start,
+            ArrowRight: start === end ? Math.min(target.value.length, end + 1) : end,
+            Home: 0,
+            End: target.value.length,
+        }[def.key];
+        setCaret(target, position);
+    } else if ((modifiers.Control || modifiers.Meta) && def.code === 'KeyA' && isTextField(target)) {
+        target.select();
+    }
+};
+
+const keyUp = (def) => {
+    if (def.key in modifiers) {
+        modifiers[def.key] = false;
+    }
+    const target = focusedElement();
+    const notCancelled = keyEvent(target, 'keyup', def);
+    // Space activates buttons, checkboxes and radios on release
+    if (def.key === ' ' && spaceArmed && notCancelled
+        && target.matches(ACTIVATABLE + ', input[type="checkbox"], input[type="radio"]')) {
+        target.click();
+    }
+    if (def.key === ' ') {
+        spaceArmed = false;
+    }
+};
+"#;
+
+/// JavaScript snippet focusing the element given by snapshot ref or locator,
+/// if any, once it is visible and enabled
+fn keyboard_focus_js(
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    options: &ActionOptions,
+) -> String {
+    if ref_num.is_none() && selector.is_none() {
+        return String::new();
+    }
+    format!(
+        r#"
+{lookup}
+if (focusedElement() !== el) {{
+    el.focus();
+    if (isTextField(el)) {{
+        setCaret(el, el.value.length);
+    }}
+}}
+"#,
+        lookup = actionable_lookup_js(ref_num, selector, Checks::Focus, options)
+    )
+}
+
+/// JavaScript code to type text into the focused element (or the element
+/// given by snapshot ref or locator) one character at a time
+/// Each character gets keydown/keypress/beforeinput/input/keyup, wrapped in
+/// Shift down/up for shifted characters; `\n` presses Enter and `\t` Tab
+pub fn type_text_js(
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    text: &str,
+    delay_ms: u64,
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{keyboard}
+{focus}
+const text = {text};
+const delayMs = {delay_ms};
+let typed = 0;
+for (const char of text) {{
+    if (char === '\r') continue;
+    const def = char === '\n' ? KEYS.Enter : char === '\t' ? KEYS.Tab : keyDefinition(char);
+    if (def.shift) keyDown(KEYS.Shift);
+    keyDown(def);
+    keyUp(def);
+    if (def.shift) keyUp(KEYS.Shift);
+    typed++;
+    if (delayMs > 0) {{
+        await new Promise((resolve) => setTimeout(resolve, delayMs));
+    }}
+}}
+return {{ success: true, typed: typed }};
+"#,
+        keyboard = KEYBOARD_JS,
+        focus = keyboard_focus_js(ref_num, selector, options),
+        text = serde_json::to_string(text).unwrap(),
+        delay_ms = delay_ms
+    )
+}
+
+/// JavaScript code to press a key or chord such as `Control+Shift+K` on the
+/// focused element (or the element given by snapshot ref or locator)
+/// Modifiers go down in order, then the key is pressed and released, then the
+/// modifiers are released in reverse order
+pub fn press_js(
+    ref_num: Option<u32>,
+    selector: Option<&str>,
+    chord: &str,
+    options: &ActionOptions,
+) -> String {
+    format!(
+        r#"
+{keyboard}
+const chord = {chord};
+// A trailing '+' is the plus key itself, as in 'Control++'
+const names = chord === '+' ? ['+'] : chord.endsWith('++') ? [...chord.slice(0, -2).split('+'), '+'] : chord.split('+');
+const defs = [];
+for (const name of names) {{
+    const def = keyDefinition(name);
+    if (!def) {{
+        return {{ success: false, error: 'Unknown key "' + name + '" in "' + chord + '"' }};
+    }}
+    defs.push(def);
+}}
+{focus}
+const key = defs.pop();
+for (const def of defs) keyDown(def);
+// Shift picks the shifted character, as on a real keyboard
+const main = modifiers.Shift && key.shifted ? KEYS[key.shifted] : key;
+keyDown(main);
+keyUp(main);
+for (const def of defs.reverse()) keyUp(def);
+return {{ success: true, key: chord }};
+"#,
+        keyboard = KEYBOARD_JS,
+        focus = keyboard_focus_js(ref_num, selector, options),
+        chord = serde_json::to_string(chord).unwrap()
+    )
+}
@@ -0,0 +1,409 @@
+// Locator engine and snapshot refs shared by snapshots and element commands
+//
+// Selectors are CSS unless they start with an engine prefix:
+//   role=button[name="Save"]   ARIA role, with name/checked/disabled/expanded/
+//                              pressed/selected/level filters
+//   text=Save                  innermost elements containing the text
+//   label=Email                form controls by label, aria-label or aria-labelledby
+//   testid=submit              [data-testid="submit"]
+//   xpath=//button             XPath (a leading // or .. also selects XPath)
+//   css=.toolbar button        explicit CSS
+//   nth=0                      pick one match (negative counts from the end)
+// Parts chained with >> are resolved inside the matches of the previous part.
+// Text values: "quoted" is exact, /regex/flags is a regex, anything else a
+// case-insensitive substring. Role names follow Playwright: quoted values are
+// case-insensitive substrings unless suffixed with s (exact) or i (exact,
+// ignoring case).
+//
+// Snapshot refs are persistent ids kept by the bridge in window.__MCP_REFS__:
+// `ids` maps elements to refs (a WeakMap, so it never keeps nodes alive) and
+// `nodes` maps refs back through WeakRefs. An element keeps its ref for as long
+// as it lives, across snapshots. Refs count up from a random per-document
+// `base`, so refs from before a reload or navigation never resolve.
+//
+// The bridge installs this once per page as part of PAGE_SCRIPTS_JS, replacing
+// any engine left by an older plugin version; commands only use the
+// window.__MCP_LOCATOR__ it exports.
+(() => {
+    function getRole(el) {
+        // Explicit ARIA role
+        if (el.getAttribute('role')) return el.getAttribute('role');
+
+        // Implicit roles based on tag
+        const tag = el.tagName.toLowerCase();
+        const type = el.getAttribute('type');
+
+        const roleMap = {
+            'a': el.href ? 'link' : null,
+            'button': 'button',
+            'input': {
+                'text': 'textbox',
+                'email': 'textbox',
+                'password': 'textbox',
+                'search': 'searchbox',
+                'tel': 'textbox',
+                'url': 'textbox',
+                'number': 'spinbutton',
+                'checkbox': 'checkbox',
+                'radio': 'radio',
+                'submit': 'button',
+                'button': 'button',
+                'reset': 'button',
+                'range': 'slider',
+            },
+            'select': 'combobox',
+            'textarea': 'textbox',
+            'img': 'img',
+            'h1': 'heading',
+            'h2': 'heading',
+            'h3': 'heading',
+            'h4': 'heading',
+            'h5': 'heading',
+            'h6': 'heading',
+            'ul': 'list',
+            'ol': 'list',
+            'li': 'listitem',
+            'table': 'table',
+            'tr': 'row',
+            'td': 'cell',
+            'th': 'columnheader',
+            'nav': 'navigation',
+            'main': 'main',
+            'header': 'banner',
+            'footer': 'contentinfo',
+            'aside': 'complementary',
+            'form': 'form',
+            'dialog': 'dialog',
+            'article': 'article',
+            'section': 'region',
+        };
+
+        if (tag === 'input') {
+            return roleMap['input'][type] || 'textbox';
+        }
+        return roleMap[tag] || null;
+    }
+
+    function getAccessibleName(el) {
+        // aria-label
+        if (el.getAttribute('aria-label')) return el.getAttribute('aria-label');
+
+        // aria-labelledby
+        const labelledBy = el.getAttribute('aria-labelledby');
+        if (labelledBy) {
+            const labelEl = document.getElementById(labelledBy);
+            if (labelEl) return labelEl.textContent.trim();
+        }
+
+        // label for input
+        if (el.id) {
+            const label = document.querySelector(`label[for="${el.id}"]`);
+            if (label) return label.textContent.trim();
+        }
+
+        // placeholder
+        if (el.placeholder) return el.placeholder;
+
+        // alt for images
+        if (el.alt) return el.alt;
+
+        // title
+        if (el.title) return el.title;
+
+        // Direct text content for certain elements
+        const tag = el.tagName.toLowerCase();
+        if (['button', 'a', 'h1', 'h2', 'h3', 'h4', 'h5', 'h6', 'label', 'li'].includes(tag)) {
+            const text = el.textContent.trim();
+            if (text && text.length < 100) return text;
+        }
+
+        return null;
+    }
+
+    const normalize = (text) => (text || '').replace(/\s+/g, ' ').trim();
+
+    const unquote = (value) => value.slice(1, -1).replace(/\\(.)/g, '$1');
+
+    const isQuoted = (value) => value.length >= 2
+        && (value[0] === '"' || value[0] === "'")
+        && value[value.length - 1] === value[0];
+
+    // Matcher for text= and label= values
+    const textMatcher = (value) => {
+        value = value.trim();
+        const regex = value.match(/^\/(.*)\/([a-z]*)$/);
+        if (regex) {
+            const re = new RegExp(regex[1], regex[2]);
+            return (text) => re.test(normalize(text));
+        }
+        if (isQuoted(value)) {
+            const exact = normalize(unquote(value));
+            return (text) => normalize(text) === exact;
+        }
+        const needle = normalize(value).toLowerCase();
+        return (text) => normalize(text).toLowerCase().includes(needle);
+    };
+
+    // Matcher for a role name filter: "x" substring, "x"i exact ignoring case, "x"s exact
+    const nameMatcher = (value) => {
+        const flagged = value.match(/^(["'])(.*)\1\s*([is]?)$/);
+        if (!flagged) return textMatcher(value);
+        const wanted = normalize(flagged[2].replace(/\\(.)/g, '$1'));
+        if (flagged[3] === 's') return (text) => normalize(text) === wanted;
+        if (flagged[3] === 'i') return (text) => normalize(text).toLowerCase() === wanted.toLowerCase();
+        return (text) => normalize(text).toLowerCase().includes(wanted.toLowerCase());
+    };
+
+    // Split on a separator outside quotes and brackets
+    const splitOutside = (input, separator) => {
+        const parts = [];
+        let quote = null;
+        let depth = 0;
+        let start = 0;
+        for (let i = 0; i < input.length; i++) {
+            const c = input[i];
+            if (quote) {
+                if (c === '\\') i++;
+                else if (c === quote) quote = null;
+            } else if (c === '"' || c === "'") {
+                quote = c;
+            } else if (depth === 0 && input.startsWith(separator, i)) {
+                parts.push(input.slice(start, i));
+                start = i + separator.length;
+                i += separator.length - 1;
+            } else if (c === '[' || c === '(') {
+                depth++;
+            } else if (c === ']' || c === ')') {
+                depth--;
+            }
+        }
+        parts.push(input.slice(start));
+        return parts;
+    };
+
+    const isRendered = (el) => el.getClientRects().length > 0
+        && window.getComputedStyle(el).visibility !== 'hidden';
+
+    const STATES = {
+        checked: (el) => (el.checked !== undefined ? el.checked : el.getAttribute('aria-checked') === 'true'),
+        disabled: (el) => el.matches(':disabled') || !!el.closest('[aria-disabled="true"]'),
+        expanded: (el) => el.getAttribute('aria-expanded') === 'true',
+        pressed: (el) => el.getAttribute('aria-pressed') === 'true',
+        selected: (el) => (el.selected !== undefined ? el.selected : el.getAttribute('aria-selected') === 'true'),
+    };
+
+    const headingLevel = (el) => {
+        const match = el.tagName.match(/^H([1-6])$/);
+        return match ? Number(match[1]) : Number(el.getAttribute('aria-level')) || null;
+    };
+
+    // role=button[name="Save"][pressed=false] -> predicate
+    const parseRole = (body) => {
+        const match = body.match(/^([a-zA-Z-]+)\s*(.*)$/s);
+        if (!match) throw new Error('expected a role name');
+        const role = match[1].toLowerCase();
+        const filters = [];
+        let rest = match[2].trim();
+        while (rest) {
+            if (rest[0] !== '[') throw new Error('unexpected "' + rest + '"');
+            const [inner] = splitOutside(rest.slice(1), ']');
+            rest = rest.slice(inner.length + 2).trim();
+            const attribute = inner.match(/^\s*([a-z-]+)\s*(?:=\s*(.*?))?\s*$/is);
+            if (!attribute) throw new Error('invalid filter [' + inner + ']');
+            const key = attribute[1].toLowerCase();
+            const value = attribute[2];
+            if (key === 'name') {
+                if (value === undefined) throw new Error('[name] needs a value');
+                const matches = nameMatcher(value);
+                filters.push((el) => matches(getAccessibleName(el) || ''));
+            } else if (key === 'level') {
+                filters.push((el) => headingLevel(el) === Number(value));
+            } else if (STATES[key]) {
+                const wanted = value === undefined || value.replace(/["']/g, '') === 'true';
+                filters.push((el) => STATES[key](el) === wanted);
+            } else {
+                throw new Error('unknown filter [' + key + ']');
+            }
+        }
+        return (el) => getRole(el) === role && isRendered(el) && filters.every((filter) => filter(el));
+    };
+
+    const SKIPPED_TAGS = ['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE', 'HEAD', 'TITLE', 'META', 'LINK'];
+
+    const descendants = (scope) => Array.from(scope.querySelectorAll('*'))
+        .filter((el) => !SKIPPED_TAGS.includes(el.tagName));
+
+    const byText = (scope, value) => {
+        const matches = textMatcher(value);
+        const ownText = (el) => (el instanceof HTMLInputElement && ['button', 'submit', 'reset'].includes(el.type)
+            ? el.value
+            : el.textContent);
+        const matched = new Set(descendants(scope).filter((el) => matches(ownText(el))));
+        // Innermost matches only, so a match is not reported with all its ancestors
+        return Array.from(matched).filter((el) => !Array.from(el.children).some((child) => matched.has(child)));
+    };
+
+    const byLabel = (scope, value) => {
+        const matches = textMatcher(value);
+        return descendants(scope).filter((el) => {
+            const texts = Array.from(el.labels || []).map((label) => label.textContent);
+            if (el.getAttribute('aria-label')) texts.push(el.getAttribute('aria-label'));
+            for (const id of (el.getAttribute('aria-labelledby') || '').split(/\s+/).filter(Boolean)) {
+                const labelEl = document.getElementById(id);
+                if (labelEl) texts.push(labelEl.textContent);
+            }
+            return texts.some((text) => matches(text));
+        });
+    };
+
+    const byXPath = (scope, expression) => {
+        // Scoped XPath must be relative to the scope, as in `>> //span`
+        if (scope !== document && expression.startsWith('/')) expression = '.' + expression;
+        const result = document.evaluate(expression, scope, null, XPathResult.ORDERED_NODE_SNAPSHOT_TYPE, null);
+        const elements = [];
+        for (let i = 0; i < result.snapshotLength; i++) {
+            const node = result.snapshotItem(i);
+            if (node.nodeType === Node.ELEMENT_NODE) elements.push(node);
+        }
+        return elements;
+    };
+
+    // Elements matching one chain part inside `scope`
+    const queryPart = (scope, part) => {
+        const engine = part.match(/^(role|text|label|testid|xpath|css)\s*=\s*(.*)$/s);
+        if (!engine) {
+            if (part.startsWith('//') || part.startsWith('..')) return byXPath(scope, part);
+            return Array.from(scope.querySelectorAll(part));
+        }
+        const [, name, body] = engine;
+        switch (name) {
+            case 'role': {
+                const matches = parseRole(body.trim());
+                return descendants(scope).filter(matches);
+            }
+            case 'text':
+                return byText(scope, body);
+            case 'label':
+                return byLabel(scope, body);
+            case 'testid': {
+                const id = isQuoted(body.trim()) ? unquote(body.trim()) : body.trim();
+                return Array.from(scope.querySelectorAll('[data-testid="' + CSS.escape(id) + '"]'));
+            }
+            case 'xpath':
+                return byXPath(scope, body.trim());
+            default:
+                return Array.from(scope.querySelectorAll(body));
+        }
+    };
+
+    const documentOrder = (a, b) => (a.compareDocumentPosition(b) & Node.DOCUMENT_POSITION_FOLLOWING ? -1 : 1);
+
+    // Every element matching a selector, in document order
+    const all = (selector) => {
+        let current = [document];
+        for (const rawPart of splitOutside(selector, '>>')) {
+            const part = rawPart.trim();
+            if (!part) throw new Error('Invalid locator "' + selector + '": empty part');
+            const nth = part.match(/^nth\s*=\s*(-?\d+)$/);
+            if (nth) {
+                const index = Number(nth[1]);
+                const picked = current[index < 0 ? current.length + index : index];
+                current = picked ? [picked] : [];
+                continue;
+            }
+            const found = new Set();
+            for (const scope of current) {
+                let matches;
+                try {
+                    matches = queryPart(scope, part);
+                } catch (e) {
+                    throw new Error('Invalid locator "' + part + '": ' + (e.message || String(e)));
+                }
+                for (const el of matches) found.add(el);
+            }
+            current = Array.from(found).sort(documentOrder);
+        }
+        return current.filter((node) => node !== document);
+    };
+
+    // Created on first use and kept on window, so refs survive HMR reloads
+    // and re-installs of this script. Refs start from a random per-document
+    // base so a ref from a previous page load never names an element of the
+    // current one.
+    const registry = () => {
+        if (!window.__MCP_REFS__) {
+            const base = (10 + Math.floor(Math.random() * 990)) * 10000;
+            window.__MCP_REFS__ = { base, next: base, ids: new WeakMap(), nodes: new Map() };
+        }
+        return window.__MCP_REFS__;
+    };
+
+    // Snapshot ref assigned to an element, if any
+    const refOf = (el) => registry().ids.get(el);
+
+    // The element's ref, assigning the next free one on first sight
+    const assignRef = (el) => {
+        const refs = registry();
+        let ref = refs.ids.get(el);
+        if (ref === undefined) {
+            ref = ++refs.next;
+            refs.ids.set(el, ref);
+            refs.nodes.set(ref, new WeakRef(el));
+        }
+        return ref;
+    };
+
+    // Drops refs whose elements were garbage collected
+    const pruneRefs = () => {
+        const refs = registry();
+        for (const [ref, node] of refs.nodes) {
+            if (!node.deref()) refs.nodes.delete(ref);
+        }
+    };
+
+    // { el } for a ref whose element is in the document, otherwise { error },
+    // with `removed` set when the element existed but is gone (including refs
+    // handed out before the page reloaded or navigated)
+    const resolveRef = (ref) => {
+        const refs = registry();
+        if (!Number.isInteger(ref) || ref < 1) {
+            return { error: 'Element ref=' + ref + ' not found. Take a snapshot first.' };
+        }
+        const node = ref > refs.base && ref <= refs.next ? refs.nodes.get(ref) : undefined;
+        const el = node && node.deref();
+        if (!el || !el.isConnected) {
+            return { removed: true, error: 'Element ref=' + ref + ' was removed from the page. Take a new snapshot.' };
+        }
+        return { el };
+    };
+
+    const describe = (el) => {
+        const role = getRole(el);
+        const name = getAccessibleName(el);
+        const ref = refOf(el);
+        let text = '<' + el.tagName.toLowerCase() + '>';
+        if (role) text += ' ' + role;
+        if (name) text += ' "' + normalize(name).slice(0, 60) + '"';
+        if (ref !== undefined) text += ' [ref=' + ref + ']';
+        return text;
+    };
+
+    // The single element matching a selector: { el } (null if none) or
+    // { error } when several match
+    const strict = (selector) => {
+        const elements = all(selector);
+        if (elements.length > 1) {
+            const candidates = elements.slice(0, 10).map((el, i) => '  ' + (i + 1) + ') ' + describe(el));
+            if (elements.length > 10) candidates.push('  ...');
+            return {
+                error: 'Locator "' + selector + '" matched ' + elements.length
+                    + ' elements; make it more specific or append >> nth=<index>:\n' + candidates.join('\n'),
+            };
+        }
+        return { el: elements[0] || null };
+    };
+
+    window.__MCP_LOCATOR__ = {
+        getRole, getAccessibleName, all, strict, describe, refOf, assignRef, pruneRefs, resolveRef,
+    };
+})();
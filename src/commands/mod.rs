@@ -1,386 +1,87 @@
 //! Command implementations for debug server
 
+pub mod annotate;
+pub mod compare;
 mod input;
 mod navigation;
 pub mod screenshot;
 mod script;
 mod snapshot;
-
-/// JavaScript code to build accessibility tree snapshot
-/// Note: This code is wrapped by eval_with_result, so it should end with a return statement
-pub const SNAPSHOT_JS: &str = r#"
-    let refCounter = 0;
-    const refMap = new Map();
-
-    // Store ref map globally for later use (click by ref, etc.)
-    window.__MCP_REF_MAP__ = refMap;
-
-    function getRole(el) {
-        // Explicit ARIA role
-        if (el.getAttribute('role')) return el.getAttribute('role');
-
-        // Implicit roles based on tag
-        const tag = el.tagName.toLowerCase();
-        const type = el.getAttribute('type');
-
-        const roleMap = {
-            'a': el.href ? 'link' : null,
-            'button': 'button',
-            'input': {
-                'text': 'textbox',
-                'email': 'textbox',
-                'password': 'textbox',
-                'search': 'searchbox',
-                'tel': 'textbox',
-                'url': 'textbox',
-                'number': 'spinbutton',
-                'checkbox': 'checkbox',
-                'radio': 'radio',
-                'submit': 'button',
-                'button': 'button',
-                'reset': 'button',
-                'range': 'slider',
-            },
-            'select': 'combobox',
-            'textarea': 'textbox',
-            'img': 'img',
-            'h1': 'heading',
-            'h2': 'heading',
-            'h3': 'heading',
-            'h4': 'heading',
-            'h5': 'heading',
-            'h6': 'heading',
-            'ul': 'list',
-            'ol': 'list',
-            'li': 'listitem',
-            'table': 'table',
-            'tr': 'row',
-            'td': 'cell',
-            'th': 'columnheader',
-            'nav': 'navigation',
-            'main': 'main',
-            'header': 'banner',
-            'footer': 'contentinfo',
-            'aside': 'complementary',
-            'form': 'form',
-            'dialog': 'dialog',
-            'article': 'article',
-            'section': 'region',
-        };
-
-        if (tag === 'input') {
-            return roleMap['input'][type] || 'textbox';
-        }
-        return roleMap[tag] || null;
-    }
-
-    function getAccessibleName(el) {
-        // aria-label
-        if (el.getAttribute('aria-label')) return el.getAttribute('aria-label');
-
-        // aria-labelledby
-        const labelledBy = el.getAttribute('aria-labelledby');
-        if (labelledBy) {
-            const labelEl = document.getElementById(labelledBy);
-            if (labelEl) return labelEl.textContent.trim();
-        }
-
-        // label for input
-        if (el.id) {
-            const label = document.querySelector(`label[for="${el.id}"]`);
-            if (label) return label.textContent.trim();
-        }
-
-        // placeholder
-        if (el.placeholder) return el.placeholder;
-
-        // alt for images
-        if (el.alt) return el.alt;
-
-        // title
-        if (el.title) return el.title;
-
-        // Direct text content for certain elements
-        const tag = el.tagName.toLowerCase();
-        if (['button', 'a', 'h1', 'h2', 'h3', 'h4', 'h5', 'h6', 'label', 'li'].includes(tag)) {
-            const text = el.textContent.trim();
-            if (text && text.length < 100) return text;
-        }
-
-        return null;
-    }
-
-    function isVisible(el) {
-        if (!el.offsetParent && el.tagName.toLowerCase() !== 'body') return false;
-        const style = window.getComputedStyle(el);
-        if (style.display === 'none' || style.visibility === 'hidden' || style.opacity === '0') return false;
-        return true;
-    }
-
-    function isInteractive(el) {
-        const tag = el.tagName.toLowerCase();
-        const interactiveTags = ['a', 'button', 'input', 'select', 'textarea'];
-        if (interactiveTags.includes(tag)) return true;
-        if (el.getAttribute('role') === 'button') return true;
-        if (el.onclick || el.getAttribute('onclick')) return true;
-        if (el.tabIndex >= 0) return true;
-        return false;
-    }
-
-    function buildTree(el, depth = 0) {
-        if (!el || el.nodeType !== Node.ELEMENT_NODE) return null;
-        if (!isVisible(el)) return null;
-
-        const role = getRole(el);
-        const name = getAccessibleName(el);
-        const isInter = isInteractive(el);
-
-        // Skip non-semantic elements unless they have children worth showing
-        const skipTags = ['div', 'span', 'script', 'style', 'noscript', 'svg', 'path'];
-        const tag = el.tagName.toLowerCase();
-
-        let children = [];
-        for (const child of el.children) {
-            const childNode = buildTree(child, depth + 1);
-            if (childNode) children.push(childNode);
-        }
-
-        // Skip non-semantic containers with single child (flatten)
-        if (skipTags.includes(tag) && !role && !isInter && children.length === 1) {
-            return children[0];
-        }
-
-        // Skip empty non-semantic elements
-        if (skipTags.includes(tag) && !role && !isInter && children.length === 0 && !name) {
-            return null;
-        }
-
-        const ref = ++refCounter;
-        refMap.set(ref, el);
-
-        const node = { ref };
-        if (role) node.role = role;
-        else node.tag = tag;
-        if (name) node.name = name;
-        if (isInter) node.interactive = true;
-
-        // Add value for form elements
-        if (el.value !== undefined && el.value !== '') {
-            node.value = el.value;
-        }
-
-        // Add checked state
-        if (el.checked !== undefined) {
-            node.checked = el.checked;
-        }
-
-        // Add disabled state
-        if (el.disabled) {
-            node.disabled = true;
-        }
-
-        if (children.length > 0) {
-            node.children = children;
-        }
-
-        return node;
-    }
-
-    function treeToText(node, indent = 0) {
-        if (!node) return '';
-
-        const prefix = '  '.repeat(indent);
-        let line = prefix + `- [ref=${node.ref}]`;
-
-        if (node.role) line += ` ${node.role}`;
-        else if (node.tag) line += ` <${node.tag}>`;
-
-        if (node.name) line += ` "${node.name}"`;
-        if (node.value) line += ` value="${node.value}"`;
-        if (node.checked) line += ` [checked]`;
-        if (node.disabled) line += ` [disabled]`;
-
-        let result = line + '\n';
-
-        if (node.children) {
-            for (const child of node.children) {
-                result += treeToText(child, indent + 1);
-            }
-        }
-
-        return result;
-    }
-
-    const tree = buildTree(document.body);
-    const snapshot = treeToText(tree);
-
-    // Build health status from HMR monitoring
-    const buildHealth = {
-        frontend: (window.__MCP_BUILD_LOGS__ || []).some(log => log.level === 'error') ? 'error' : 'healthy',
-        hmrConnected: window.__MCP_HMR_STATUS__ === 'connected',
-        lastError: (window.__MCP_BUILD_LOGS__ || []).filter(log => log.level === 'error').slice(-1)[0] || null,
-    };
-
-    // Include warning if there are build errors
-    const result = {
-        window: window.__MCP_WINDOW_LABEL__ || 'main',
-        url: window.location.href,
-        title: document.title,
-        snapshot: snapshot,
-        buildHealth: buildHealth,
-    };
-
-    if (buildHealth.frontend === 'error' && buildHealth.lastError) {
-        result.warning = `Build error: ${buildHealth.lastError.message}`;
-        if (buildHealth.lastError.file) {
-            result.warning += ` (${buildHealth.lastError.file}:${buildHealth.lastError.line || '?'})`;
-        }
-    }
-
-    return result;
-"#;
-
-/// JavaScript code to click an element by CSS selector
-pub fn click_js(selector: &str) -> String {
+mod wait;
+
+pub use input::{
+    click_js, drag_js, fill_js, mouse_js, pointer_action_js, press_js, read_input_files,
+    select_option_js, set_checked_js, set_input_files_js, type_text_js, ActionOptions, MouseButton,
+};
+pub use navigation::{get_popups_js, intercept_popups_js};
+pub use snapshot::{snapshot_js, SnapshotOptions};
+pub use wait::{wait_for_idle_js, wait_for_js, IdleOptions, WaitCondition};
+
+/// JavaScript code to click whatever is at a viewport point (CSS pixels)
+/// Dispatches the pointer and mouse events of a real click on the topmost element
+pub fn click_at_js(x: f64, y: f64) -> String {
     format!(
         r#"
-const el = document.querySelector({selector});
-if (!el) {{
-    return {{ success: false, error: 'Element not found: {raw_selector}' }};
+{lookup}
+const init = {{ bubbles: true, cancelable: true, composed: true, view: window, clientX: x, clientY: y, button: 0 }};
+const pointer = {{ pointerId: 1, pointerType: 'mouse', isPrimary: true }};
+el.dispatchEvent(new PointerEvent('pointerdown', {{ ...init, ...pointer, buttons: 1 }}));
+el.dispatchEvent(new MouseEvent('mousedown', {{ ...init, buttons: 1 }}));
+const focusable = el.closest('input, textarea, select, button, a[href], [tabindex], [contenteditable]');
+if (focusable) {{
+    focusable.focus({{ preventScroll: true }});
 }}
-el.click();
-return {{ success: true }};
+el.dispatchEvent(new PointerEvent('pointerup', {{ ...init, ...pointer }}));
+el.dispatchEvent(new MouseEvent('mouseup', init));
+el.dispatchEvent(new MouseEvent('click', {{ ...init, detail: 1 }}));
+return {{ success: true, x: x, y: y, element: describe(el) }};
 "#,
-        selector = serde_json::to_string(selector).unwrap(),
-        raw_selector = selector
+        lookup = point_lookup_js(x, y)
     )
 }
 
-/// JavaScript code to click an element by ref number
-pub fn click_ref_js(ref_num: u32) -> String {
+/// JavaScript code to describe the topmost element at a viewport point (CSS pixels)
+pub fn element_at_js(x: f64, y: f64) -> String {
     format!(
         r#"
-const refMap = window.__MCP_REF_MAP__;
-if (!refMap) {{
-    return {{ success: false, error: 'No snapshot taken yet. Call snapshot first.' }};
-}}
-const el = refMap.get({ref_num});
-if (!el) {{
-    return {{ success: false, error: 'Element ref={ref_num} not found. Snapshot may be stale.' }};
-}}
-el.scrollIntoView({{ behavior: 'instant', block: 'center' }});
-el.click();
-return {{ success: true }};
+{lookup}
+return {{ success: true, x: x, y: y, element: describe(el) }};
 "#,
-        ref_num = ref_num
+        lookup = point_lookup_js(x, y)
     )
 }
 
-/// JavaScript code to fill an input by CSS selector
-/// Uses native value setter to properly trigger React's synthetic event system
-pub fn fill_js(selector: &str, value: &str) -> String {
+/// JavaScript code to list the elements matching a locator, in document order
+/// Reports the match count and describes up to `limit` matches
+pub fn locate_js(selector: &str, limit: usize) -> String {
     format!(
         r#"
-const el = document.querySelector({selector});
-if (!el) {{
-    return {{ success: false, error: 'Element not found: {raw_selector}' }};
-}}
-
-// Get the native value setter to bypass React's synthetic event system
-// This is required for React controlled inputs to properly update state
-const tagName = el.tagName.toLowerCase();
-const prototype = tagName === 'textarea' ? window.HTMLTextAreaElement.prototype : window.HTMLInputElement.prototype;
-const nativeValueSetter = Object.getOwnPropertyDescriptor(prototype, 'value')?.set;
-
-if (nativeValueSetter) {{
-    nativeValueSetter.call(el, {value});
-}} else {{
-    el.value = {value};
+{require_locator}
+let elements;
+try {{
+    elements = window.__MCP_LOCATOR__.all({selector});
+}} catch (e) {{
+    return {{ success: false, error: e.message }};
 }}
-
-// Dispatch input event with bubbles to trigger React's onChange
-const inputEvent = new Event('input', {{ bubbles: true, cancelable: true }});
-// React 16+ uses this property to track the event
-Object.defineProperty(inputEvent, 'simulated', {{ value: true }});
-el.dispatchEvent(inputEvent);
-
-// Also dispatch change event for completeness
-el.dispatchEvent(new Event('change', {{ bubbles: true }}));
-
-return {{ success: true }};
+return {{
+    success: true,
+    count: elements.length,
+    elements: elements.slice(0, {limit}).map((el) => {{
+        const r = el.getBoundingClientRect();
+        return {{
+            tag: el.tagName.toLowerCase(),
+            role: window.__MCP_LOCATOR__.getRole(el) || undefined,
+            name: window.__MCP_LOCATOR__.getAccessibleName(el) || undefined,
+            ref: window.__MCP_LOCATOR__.refOf(el),
+            visible: r.width > 0 && r.height > 0 && window.getComputedStyle(el).visibility !== 'hidden',
+            rect: {{ x: r.left, y: r.top, width: r.width, height: r.height }},
+        }};
+    }}),
+}};
 "#,
+        require_locator = REQUIRE_LOCATOR_JS,
         selector = serde_json::to_string(selector).unwrap(),
-        raw_selector = selector,
-        value = serde_json::to_string(value).unwrap()
-    )
-}
-
-/// JavaScript code to fill an input by ref number
-/// Uses native value setter to properly trigger React's synthetic event system
-pub fn fill_ref_js(ref_num: u32, value: &str) -> String {
-    format!(
-        r#"
-const refMap = window.__MCP_REF_MAP__;
-if (!refMap) {{
-    return {{ success: false, error: 'No snapshot taken yet. Call snapshot first.' }};
-}}
-const el = refMap.get({ref_num});
-if (!el) {{
-    return {{ success: false, error: 'Element ref={ref_num} not found. Snapshot may be stale.' }};
-}}
-el.scrollIntoView({{ behavior: 'instant', block: 'center' }});
-el.focus();
-
-// Get the native value setter to bypass React's synthetic event system
-// This is required for React controlled inputs to properly update state
-const tagName = el.tagName.toLowerCase();
-const prototype = tagName === 'textarea' ? window.HTMLTextAreaElement.prototype : window.HTMLInputElement.prototype;
-const nativeValueSetter = Object.getOwnPropertyDescriptor(prototype, 'value')?.set;
-
-if (nativeValueSetter) {{
-    nativeValueSetter.call(el, {value});
-}} else {{
-    el.value = {value};
-}}
-
-// Dispatch input event with bubbles to trigger React's onChange
-const inputEvent = new Event('input', {{ bubbles: true, cancelable: true }});
-// React 16+ uses this property to track the event
-Object.defineProperty(inputEvent, 'simulated', {{ value: true }});
-el.dispatchEvent(inputEvent);
-
-// Also dispatch change event for completeness
-el.dispatchEvent(new Event('change', {{ bubbles: true }}));
-
-return {{ success: true }};
-"#,
-        ref_num = ref_num,
-        value = serde_json::to_string(value).unwrap()
-    )
-}
-
-/// JavaScript code to press a key
-pub fn press_key_js(key: &str) -> String {
-    format!(
-        r#"
-const activeEl = document.activeElement || document.body;
-const keyEvent = new KeyboardEvent('keydown', {{
-    key: {key},
-    bubbles: true,
-    cancelable: true
-}});
-activeEl.dispatchEvent(keyEvent);
-
-const keyUpEvent = new KeyboardEvent('keyup', {{
-    key: {key},
-    bubbles: true,
-    cancelable: true
-}});
-activeEl.dispatchEvent(keyUpEvent);
-
-return {{ success: true }};
-"#,
-        key = serde_json::to_string(key).unwrap()
+        limit = limit
     )
 }
 
@@ -494,69 +195,391 @@ return result;
     )
 }
 
-/// JavaScript code to take a screenshot
-pub const SCREENSHOT_JS: &str = r#"
-    // Load html2canvas if not already loaded
-    if (!window.html2canvas) {
-        const script = document.createElement('script');
-        script.src = 'https://cdnjs.cloudflare.com/ajax/libs/html2canvas/1.4.1/html2canvas.min.js';
-        document.head.appendChild(script);
-        await new Promise(resolve => script.onload = resolve);
-    }
+/// JavaScript code to render a screenshot in the page, used when native capture fails
+///
+/// `options` may contain `clip` (`{ x, y, width, height }`, viewport-relative CSS
+/// pixels) to capture only that region, `fullPage` to render the whole document,
+/// `image` (see [`screenshot::ImageOptions`]) to control the output encoding, and
+/// `masks` (viewport-relative rects) with `maskColor` to paint over regions.
+/// The result carries a `transform` like native captures (see
+/// [`screenshot::ImageTransform`]) and `rendered: true`, since renders are not
+/// pixel-identical to captures. Renders with the rasterizer installed by
+/// [`PAGE_SCRIPTS_JS`].
+pub fn screenshot_js(options: &serde_json::Value) -> String {
+    format!("const options = {};\n{}", options, SCREENSHOT_JS)
+}
 
-    // html2canvas doesn't support modern CSS color functions (oklch, oklab, lch, lab)
-    // Workaround: disable stylesheets with these colors and apply computed RGB inline
+/// JavaScript code to get an element's bounding box for element screenshots
+/// Only the part of the element inside the viewport is reported
+pub fn element_rect_js(ref_num: Option<u32>, selector: Option<&str>) -> String {
+    format!(
+        r#"
+{lookup}
+el.scrollIntoView({{ behavior: 'instant', block: 'nearest', inline: 'nearest' }});
+// Let layout and paint settle after scrolling
+await new Promise(resolve => requestAnimationFrame(() => requestAnimationFrame(resolve)));
+
+const r = el.getBoundingClientRect();
+const left = Math.max(r.left, 0);
+const top = Math.max(r.top, 0);
+const right = Math.min(r.right, window.innerWidth);
+const bottom = Math.min(r.bottom, window.innerHeight);
+if (right <= left || bottom <= top) {{
+    return {{ success: false, error: 'Element has no visible area' }};
+}}
+return {{
+    success: true,
+    rect: {{ x: left, y: top, width: right - left, height: bottom - top }},
+    devicePixelRatio: window.devicePixelRatio || 1,
+}};
+"#,
+        lookup = element_lookup_js(ref_num, selector)
+    )
+}
 
-    // Disable stylesheets containing unsupported color functions
-    const disabledStyles = [];
-    document.querySelectorAll('style').forEach((s) => {
-        if (s.textContent && /(oklch|oklab|lch|lab)\(/i.test(s.textContent)) {
-            disabledStyles.push({ el: s, content: s.textContent });
-            s.textContent = '/* temporarily disabled for screenshot */';
-        }
-    });
+/// JavaScript code to prepare the page for a deterministic capture
+///
+/// Waits for web fonts, hides the text caret and, with `disable_animations`,
+/// disables transitions and pauses running animations: finite ones at their
+/// end state, infinite ones at their start. Undone by [`RESTORE_CAPTURE_JS`],
+/// which puts every paused animation back where it was and resumes it.
+pub fn prepare_capture_js(disable_animations: bool) -> String {
+    format!(
+        r#"
+await document.fonts.ready;
 
-    // Apply computed styles inline to preserve colors
-    // Skip SVG and icon elements to avoid breaking their rendering
-    const elements = document.body.querySelectorAll('*');
-    const inlineBackups = [];
-    elements.forEach((el) => {
-        // Skip SVG elements and their children - they handle colors differently
-        if (el instanceof SVGElement || el.closest('svg')) {
-            return;
-        }
+let style = document.getElementById('__mcp_capture_style__');
+if (!style) {{
+    style = document.createElement('style');
+    style.id = '__mcp_capture_style__';
+    (document.head || document.documentElement).appendChild(style);
+}}
+let css = '*, *::before, *::after {{ caret-color: transparent !important; }}';
+window.__MCP_PAUSED_ANIMATIONS__ = [];
+if ({disable_animations}) {{
+    css += '*, *::before, *::after {{ transition-duration: 0s !important; transition-delay: 0s !important; }}';
+    for (const animation of document.getAnimations()) {{
+        if (animation.playState !== 'running') continue;
+        const timing = animation.effect ? animation.effect.getComputedTiming() : null;
+        window.__MCP_PAUSED_ANIMATIONS__.push({{ animation: animation, currentTime: animation.currentTime }});
+        animation.pause();
+        animation.currentTime = timing && timing.endTime !== Infinity ? timing.endTime : 0;
+    }}
+}}
+style.textContent = css;
 
-        // Skip common icon element patterns (font icons, icon components)
-        const tagName = el.tagName.toLowerCase();
-        const classList = [...el.classList];
-        if (tagName === 'i' ||
-            classList.some(c => c === 'icon' || c === 'material-icons' ||
-                /^fa[srbl]?$/.test(c) || c.startsWith('lucide-'))) {
-            return;
-        }
+// Let the styles apply before capturing; hidden pages never run animation frames
+await Promise.race([
+    new Promise(resolve => requestAnimationFrame(() => requestAnimationFrame(resolve))),
+    new Promise(resolve => setTimeout(resolve, 100)),
+]);
+return {{ success: true }};
+"#,
+        disable_animations = disable_animations
+    )
+}
+
+/// JavaScript code to undo [`prepare_capture_js`]
+pub const RESTORE_CAPTURE_JS: &str = r#"
+const style = document.getElementById('__mcp_capture_style__');
+if (style) {
+    style.remove();
+}
+for (const paused of window.__MCP_PAUSED_ANIMATIONS__ || []) {
+    paused.animation.currentTime = paused.currentTime;
+    paused.animation.play();
+}
+window.__MCP_PAUSED_ANIMATIONS__ = [];
+return { success: true };
+"#;
+
+/// JavaScript code to get the bounding boxes of elements to mask
+/// Locators mask every match; refs must point at elements still in the page
+pub fn mask_rects_js(targets: &[screenshot::MaskTarget]) -> String {
+    format!(
+        r#"
+{require_locator}
+const targets = {targets};
+const rects = [];
+for (const target of targets) {{
+    let elements;
+    if (typeof target === 'number') {{
+        const found = window.__MCP_LOCATOR__.resolveRef(target);
+        if (found.error) {{
+            return {{ success: false, error: found.error }};
+        }}
+        elements = [found.el];
+    }} else {{
+        try {{
+            elements = window.__MCP_LOCATOR__.all(target);
+        }} catch (e) {{
+            return {{ success: false, error: e.message }};
+        }}
+    }}
+    for (const el of elements) {{
+        const r = el.getBoundingClientRect();
+        if (r.width > 0 && r.height > 0) {{
+            rects.push({{ x: r.left, y: r.top, width: r.width, height: r.height }});
+        }}
+    }}
+}}
+return {{ success: true, rects: rects }};
+"#,
+        require_locator = REQUIRE_LOCATOR_JS,
+        targets = serde_json::to_string(targets).unwrap()
+    )
+}
+
+/// JavaScript code to get the visible bounding box of every live ref
+/// Rects are clipped to the viewport; refs that are removed or off-screen are skipped
+pub const REF_RECTS_JS: &str = r#"
+const registry = window.__MCP_REFS__;
+if (!registry || registry.next === registry.base) {
+    return { success: false, error: 'No snapshot taken yet. Call snapshot first.' };
+}
+const root = document.documentElement;
+const viewport = { width: root.clientWidth, height: root.clientHeight };
+const refs = [];
+for (const [ref, node] of registry.nodes) {
+    const el = node.deref();
+    if (!el || !el.isConnected) continue;
+    const r = el.getBoundingClientRect();
+    const left = Math.max(r.left, 0);
+    const top = Math.max(r.top, 0);
+    const right = Math.min(r.right, viewport.width);
+    const bottom = Math.min(r.bottom, viewport.height);
+    if (right <= left || bottom <= top) continue;
+    refs.push({ ref: ref, x: left, y: top, width: right - left, height: bottom - top });
+}
+return { success: true, refs: refs };
+"#;
+
+/// JavaScript code to select the scroll container for full-page screenshots
+///
+/// Uses the element matching `selector` (a locator) if given, otherwise the document when it scrolls, otherwise the
+/// largest scrollable element (apps often lock the body and scroll an inner pane).
+/// Reports the container's visible rect (viewport CSS pixels) and scroll metrics.
+pub fn scroll_container_js(selector: Option<&str>) -> String {
+    format!(
+        r#"
+{require_locator}
+const selector = {selector};
+const root = document.scrollingElement || document.documentElement;
+let el;
+if (selector) {{
+    let found;
+    try {{
+        found = window.__MCP_LOCATOR__.strict(selector);
+    }} catch (e) {{
+        return {{ success: false, error: e.message }};
+    }}
+    if (found.error) {{
+        return {{ success: false, error: found.error }};
+    }}
+    el = found.el;
+    if (!el) {{
+        return {{ success: false, error: 'Scroll container not found: ' + selector }};
+    }}
+}} else if (root.scrollHeight > root.clientHeight + 1) {{
+    el = root;
+}} else {{
+    let bestArea = 0;
+    for (const candidate of document.querySelectorAll('*')) {{
+        if (candidate.scrollHeight <= candidate.clientHeight + 1) continue;
+        const overflowY = window.getComputedStyle(candidate).overflowY;
+        if (!['auto', 'scroll', 'overlay'].includes(overflowY)) continue;
+        const area = candidate.clientWidth * candidate.clientHeight;
+        if (area > bestArea) {{
+            el = candidate;
+            bestArea = area;
+        }}
+    }}
+    el = el || root;
+}}
+window.__MCP_SCROLL_CONTAINER__ = el;
+
+const isRoot = el === root || el === document.body;
+let rect;
+if (isRoot) {{
+    rect = {{ x: 0, y: 0, width: root.clientWidth, height: root.clientHeight }};
+}} else {{
+    const r = el.getBoundingClientRect();
+    rect = {{ x: r.left + el.clientLeft, y: r.top + el.clientTop, width: el.clientWidth, height: el.clientHeight }};
+}}
+return {{
+    success: true,
+    rect: rect,
+    scrollTop: el.scrollTop,
+    scrollHeight: el.scrollHeight,
+    clientHeight: rect.height,
+    devicePixelRatio: window.devicePixelRatio || 1,
+}};
+"#,
+        require_locator = REQUIRE_LOCATOR_JS,
+        selector = serde_json::to_string(&selector).unwrap()
+    )
+}
+
+/// JavaScript code to scroll the container chosen by [`scroll_container_js`]
+/// Waits for the next paint and reports the resulting scroll offset
+pub fn scroll_container_to_js(top: f64) -> String {
+    format!(
+        r#"
+const el = window.__MCP_SCROLL_CONTAINER__;
+if (!el) {{
+    return {{ success: false, error: 'No scroll container selected' }};
+}}
+el.scrollTop = {top};
+await new Promise(resolve => requestAnimationFrame(() => requestAnimationFrame(resolve)));
+return {{ success: true, scrollTop: el.scrollTop }};
+"#,
+        top = top
+    )
+}
+
+/// JavaScript snippet resolving `el` from a snapshot ref or locator
+/// Returns an error result from the enclosing script if the element is missing
+fn element_lookup_js(ref_num: Option<u32>, selector: Option<&str>) -> String {
+    match ref_num {
+        Some(ref_num) => format!(
+            r#"
+{require_locator}
+const found = window.__MCP_LOCATOR__.resolveRef({ref_num});
+if (found.error) {{
+    return {{ success: false, error: found.error }};
+}}
+const el = found.el;
+"#,
+            require_locator = REQUIRE_LOCATOR_JS,
+            ref_num = ref_num
+        ),
+        None => format!(
+            r#"
+{require_locator}
+let found;
+try {{
+    found = window.__MCP_LOCATOR__.strict({selector});
+}} catch (e) {{
+    return {{ success: false, error: e.message }};
+}}
+if (found.error) {{
+    return {{ success: false, error: found.error }};
+}}
+const el = found.el;
+if (!el) {{
+    return {{ success: false, error: 'Element not found: ' + {selector} }};
+}}
+"#,
+            require_locator = REQUIRE_LOCATOR_JS,
+            selector = serde_json::to_string(selector.unwrap_or("")).unwrap()
+        ),
+    }
+}
 
-        const computed = window.getComputedStyle(el);
-        const oldStyle = el.getAttribute('style') || '';
-        inlineBackups.push({ el, oldStyle });
-        // Apply key color properties as inline styles
-        el.style.color = computed.color;
-        el.style.backgroundColor = computed.backgroundColor;
-        el.style.borderColor = computed.borderColor;
+/// JavaScript snippet resolving `el` as the topmost element at viewport point `x`, `y`
+/// Also defines `describe(el)`, which reports the element with its nearest snapshot ref
+fn point_lookup_js(x: f64, y: f64) -> String {
+    format!(
+        r#"
+const x = {x};
+const y = {y};
+if (x < 0 || y < 0 || x >= window.innerWidth || y >= window.innerHeight) {{
+    return {{ success: false, error: 'Point (' + Math.round(x) + ', ' + Math.round(y) + ') is outside the viewport. Take a new screenshot.' }};
+}}
+const el = document.elementFromPoint(x, y);
+if (!el) {{
+    return {{ success: false, error: 'No element at (' + Math.round(x) + ', ' + Math.round(y) + ')' }};
+}}
+const describe = (el) => {{
+    const refs = window.__MCP_REFS__ ? window.__MCP_REFS__.ids : new WeakMap();
+    let refNode = el;
+    while (refNode && !refs.has(refNode)) {{
+        refNode = refNode.parentElement;
+    }}
+    const r = el.getBoundingClientRect();
+    const text = (el.innerText || el.textContent || '').trim();
+    return {{
+        tag: el.tagName.toLowerCase(),
+        id: el.id || undefined,
+        role: el.getAttribute('role') || undefined,
+        text: text ? text.slice(0, 100) : undefined,
+        ref: refNode ? refs.get(refNode) : undefined,
+        rect: {{ x: r.left, y: r.top, width: r.width, height: r.height }},
+    }};
+}};
+"#,
+        x = x,
+        y = y
+    )
+}
+
+/// JavaScript snippet failing the enclosing script unless the locator engine
+/// (`window.__MCP_LOCATOR__`, installed with [`PAGE_SCRIPTS_JS`]) is available
+const REQUIRE_LOCATOR_JS: &str = r#"
+if (!window.__MCP_LOCATOR__) {
+    throw new Error('Locator engine is not installed. Reload the page or call initMcpBridge().');
+}
+"#;
+
+/// Scripts the bridge installs once per page, for commands to use
+///
+/// Defines `window.__MCP_LOCATOR__`, the locator engine and snapshot refs of
+/// locator.js, always replacing an existing one so the engine matches this
+/// plugin. Then `window.__MCP_RASTERIZE__`, the DOM rasterizer of rasterize.js
+/// for [`screenshot_js`], embedded so the fallback works offline and under a
+/// strict CSP.
+pub const PAGE_SCRIPTS_JS: &str = concat!(include_str!("locator.js"), include_str!("rasterize.js"));
+
+/// JavaScript code to take a screenshot (body of [`screenshot_js`], expects `options`)
+const SCREENSHOT_JS: &str = r#"
+    if (!window.__MCP_RASTERIZE__) {
+        throw new Error('Screenshot renderer is not installed. Reload the page or call initMcpBridge().');
+    }
+
+    // Cover masked regions with overlays positioned in document coordinates
+    const maskOverlays = (options.masks || []).map((r) => {
+        const overlay = document.createElement('div');
+        overlay.style.cssText = 'position:absolute;pointer-events:none;z-index:2147483647;' +
+            'left:' + (r.x + window.scrollX) + 'px;top:' + (r.y + window.scrollY) + 'px;' +
+            'width:' + r.width + 'px;height:' + r.height + 'px;' +
+            'background:' + (options.maskColor || '#FF00FF');
+        document.body.appendChild(overlay);
+        return overlay;
     });
 
     try {
-        const canvas = await window.html2canvas(document.body, {
-            useCORS: true,
-            allowTaint: true,
-            scale: 1.0,  // Use 1.0 for accurate rendering on Retina displays
-            logging: false
-        });
-
-        // Resize to max 1280x720 to limit output size
-        const maxWidth = 1280;
-        const maxHeight = 720;
-        let width = canvas.width;
-        let height = canvas.height;
+        // Use scale 1.0 for accurate rendering on Retina displays
+        const renderOptions = { scale: 1.0 };
+        // Viewport point shown at the image's top-left corner
+        let originX = 0;
+        let originY = 0;
+        // Clip to a viewport region (converted to document coordinates)
+        if (options.clip) {
+            originX = options.clip.x;
+            originY = options.clip.y;
+            renderOptions.x = options.clip.x + window.scrollX;
+            renderOptions.y = options.clip.y + window.scrollY;
+            renderOptions.width = options.clip.width;
+            renderOptions.height = options.clip.height;
+        } else if (options.fullPage) {
+            const root = document.scrollingElement || document.documentElement;
+            renderOptions.x = 0;
+            renderOptions.y = 0;
+            renderOptions.width = root.clientWidth;
+            renderOptions.height = root.scrollHeight;
+            // Render very tall pages at a lower scale so the canvas stays within 32 MP
+            renderOptions.scale = Math.min(1, Math.sqrt(32000000 / (root.clientWidth * root.scrollHeight)));
+            originX = -window.scrollX;
+            originY = -window.scrollY;
+        }
+        const canvas = await window.__MCP_RASTERIZE__(renderOptions);
+
+        // Resize to max 1280x720 (or 1280 wide for full pages) unless overridden
+        const image = options.image || {};
+        const maxWidth = image.max_width || 1280;
+        const maxHeight = image.max_height || (options.fullPage ? 16384 : 720);
+        let width = Math.max(1, Math.round(canvas.width * (image.scale || 1)));
+        let height = Math.max(1, Math.round(canvas.height * (image.scale || 1)));
 
         if (width > maxWidth || height > maxHeight) {
             const ratio = Math.min(maxWidth / width, maxHeight / height);
@@ -569,23 +592,29 @@ pub const SCREENSHOT_JS: &str = r#"
         resized.height = height;
         resized.getContext('2d').drawImage(canvas, 0, 0, width, height);
 
-        // JPEG with 0.6 quality for smaller file size
-        const dataUrl = resized.toDataURL('image/jpeg', 0.6);
-
-        // Restore original stylesheets
-        disabledStyles.forEach(d => { d.el.textContent = d.content; });
-        // Restore original inline styles
-        inlineBackups.forEach(b => { b.el.setAttribute('style', b.oldStyle); });
+        // Same defaults as native captures: PNG, and JPEG quality 80
+        const format = image.format || 'png';
+        const quality = image.quality ? image.quality / 100 : 0.8;
+        const dataUrl = resized.toDataURL('image/' + format, quality);
 
+        // Rendered at renderOptions.scale canvas pixels per CSS pixel
+        const scale = renderOptions.scale * width / canvas.width;
         return {
             data: dataUrl,
             width: width,
-            height: height
+            height: height,
+            rendered: true,
+            transform: {
+                scale: scale,
+                offset_x: -originX * scale,
+                offset_y: -originY * scale,
+                device_pixel_ratio: window.devicePixelRatio || 1,
+                resize_ratio: scale,
+            },
         };
     } catch (e) {
-        // Restore on error
-        disabledStyles.forEach(d => { d.el.textContent = d.content; });
-        inlineBackups.forEach(b => { b.el.setAttribute('style', b.oldStyle); });
         throw new Error('Screenshot failed: ' + e.message);
+    } finally {
+        maskOverlays.forEach(o => o.remove());
     }
 "#;
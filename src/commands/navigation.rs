@@ -1 +1,92 @@
 //! Navigation commands
+
+/// JavaScript code to intercept `window.open` and `target="_blank"` links
+///
+/// Modes:
+/// - `report`: record the popup request and let it open
+/// - `redirect`: record the popup request and navigate the current webview instead
+/// - `off`: restore the native behavior
+pub fn intercept_popups_js(mode: &str) -> String {
+    format!(
+        r#"
+const mode = {mode};
+if (!['report', 'redirect', 'off'].includes(mode)) {{
+    return {{ success: false, error: 'Unknown popup mode: ' + mode + ' (expected report, redirect or off)' }};
+}}
+
+window.__MCP_POPUPS__ = window.__MCP_POPUPS__ || [];
+window.__MCP_POPUP_MODE__ = mode;
+if (!window.__MCP_ORIGINAL_OPEN__) {{
+    window.__MCP_ORIGINAL_OPEN__ = window.open;
+}}
+
+// Record a popup request and apply the current mode
+// Returns true if the request was redirected (native popup must not open)
+window.__MCP_HANDLE_POPUP__ = function(url, target, features, source) {{
+    const currentMode = window.__MCP_POPUP_MODE__;
+    if (!currentMode || currentMode === 'off') return false;
+
+    const resolved = url ? new URL(String(url), window.location.href).href : 'about:blank';
+    window.__MCP_POPUPS__.push({{
+        url: resolved,
+        target: target || '_blank',
+        features: features || '',
+        source: source,
+        action: currentMode === 'redirect' ? 'redirected' : 'opened',
+        timestamp: Date.now(),
+    }});
+    if (window.__MCP_POPUPS__.length > 1000) {{
+        window.__MCP_POPUPS__.shift();
+    }}
+
+    if (currentMode !== 'redirect') return false;
+    if (url) {{
+        window.location.href = resolved;
+    }}
+    return true;
+}};
+
+if (mode === 'off') {{
+    window.open = window.__MCP_ORIGINAL_OPEN__;
+    return {{ success: true, mode: mode }};
+}}
+
+window.open = function(url, target, features) {{
+    if (window.__MCP_HANDLE_POPUP__(url, target, features, 'window.open')) {{
+        return window;
+    }}
+    return window.__MCP_ORIGINAL_OPEN__.call(window, url, target, features);
+}};
+
+// Links with target="_blank" open popups without going through window.open
+if (!window.__MCP_POPUP_LINK_LISTENER__) {{
+    window.__MCP_POPUP_LINK_LISTENER__ = true;
+    document.addEventListener('click', (event) => {{
+        const link = event.target instanceof Element ? event.target.closest('a[target="_blank"]') : null;
+        if (!link || !link.href) return;
+        if (window.__MCP_HANDLE_POPUP__(link.href, link.target, '', 'link')) {{
+            event.preventDefault();
+        }}
+    }}, true);
+}}
+
+return {{ success: true, mode: mode }};
+"#,
+        mode = serde_json::to_string(mode).unwrap()
+    )
+}
+
+/// JavaScript code to get intercepted popup requests
+pub fn get_popups_js(clear: bool) -> String {
+    format!(
+        r#"
+const popups = window.__MCP_POPUPS__ || [];
+const result = {{ mode: window.__MCP_POPUP_MODE__ || 'off', popups: [...popups] }};
+if ({clear}) {{
+    window.__MCP_POPUPS__ = [];
+}}
+return result;
+"#,
+        clear = if clear { "true" } else { "false" }
+    )
+}
@@ -0,0 +1,124 @@
+// DOM rasterizer for the JS screenshot fallback
+//
+// Renders a copy of the document inside an SVG <foreignObject>, so the
+// browser's own engine does layout and painting (including modern CSS that
+// canvas-based reimplementations miss). SVG images cannot load external
+// resources, so stylesheets and images are inlined first. Installed once per
+// page by the bridge, see `PAGE_SCRIPTS_JS`.
+(() => {
+    if (window.__MCP_RASTERIZE__) return;
+
+    // Collect every readable stylesheet rule; cross-origin sheets are skipped
+    const collectCss = () => {
+        let css = '';
+        for (const sheet of document.styleSheets) {
+            try {
+                for (const rule of sheet.cssRules) {
+                    css += rule.cssText + '\n';
+                }
+            } catch (e) {
+                // Cross-origin stylesheet without CORS
+            }
+        }
+        return css;
+    };
+
+    // Convert a loaded image or canvas to a data URL, or null if tainted
+    const toDataUrl = (source, width, height) => {
+        try {
+            const canvas = document.createElement('canvas');
+            canvas.width = width;
+            canvas.height = height;
+            canvas.getContext('2d').drawImage(source, 0, 0);
+            return canvas.toDataURL();
+        } catch (e) {
+            return null;
+        }
+    };
+
+    // Clone the document, carrying over state that cloneNode does not copy
+    const cloneDocument = () => {
+        const source = document.documentElement;
+        const clone = source.cloneNode(true);
+        const sources = [source, ...source.querySelectorAll('*')];
+        const clones = [clone, ...clone.querySelectorAll('*')];
+
+        for (let i = 0; i < sources.length && i < clones.length; i++) {
+            const from = sources[i];
+            const to = clones[i];
+            if (from instanceof HTMLInputElement) {
+                if (from.type === 'checkbox' || from.type === 'radio') {
+                    to.toggleAttribute('checked', from.checked);
+                } else if (from.type !== 'file') {
+                    to.setAttribute('value', from.value);
+                }
+            } else if (from instanceof HTMLTextAreaElement) {
+                to.textContent = from.value;
+            } else if (from instanceof HTMLSelectElement) {
+                [...to.options].forEach((option, j) => {
+                    option.toggleAttribute('selected', from.options[j].selected);
+                });
+            } else if (from instanceof HTMLImageElement && from.complete && from.naturalWidth) {
+                const url = toDataUrl(from, from.naturalWidth, from.naturalHeight);
+                if (url) {
+                    to.src = url;
+                    to.removeAttribute('srcset');
+                }
+            } else if (from instanceof HTMLCanvasElement) {
+                const url = toDataUrl(from, from.width, from.height);
+                if (url) {
+                    const img = document.createElement('img');
+                    img.src = url;
+                    img.className = from.className;
+                    img.setAttribute('style', from.getAttribute('style') || '');
+                    img.width = from.clientWidth;
+                    img.height = from.clientHeight;
+                    to.replaceWith(img);
+                }
+            }
+        }
+
+        // Scripts must not run; stylesheets are replaced by the inlined rules
+        clone.querySelectorAll('script, link[rel="stylesheet"], style').forEach(el => el.remove());
+        const style = document.createElement('style');
+        style.textContent = collectCss();
+        (clone.querySelector('head') || clone).appendChild(style);
+        clone.setAttribute('xmlns', 'http://www.w3.org/1999/xhtml');
+        return clone;
+    };
+
+    const loadImage = (url) => new Promise((resolve, reject) => {
+        const img = new Image();
+        img.onload = () => resolve(img);
+        img.onerror = () => reject(new Error('Failed to render document image'));
+        img.src = url;
+    });
+
+    // Render a region of the document (document CSS pixels) to a canvas
+    // options: { x, y, width, height, scale } (default: the current viewport)
+    window.__MCP_RASTERIZE__ = async (options = {}) => {
+        const root = document.documentElement;
+        const x = options.x ?? window.scrollX;
+        const y = options.y ?? window.scrollY;
+        const width = Math.max(1, Math.round(options.width ?? root.clientWidth));
+        const height = Math.max(1, Math.round(options.height ?? root.clientHeight));
+        const scale = options.scale ?? 1;
+        const docWidth = Math.max(root.scrollWidth, x + width);
+        const docHeight = Math.max(root.scrollHeight, y + height);
+
+        const xhtml = new XMLSerializer().serializeToString(cloneDocument());
+        const svg = '<svg xmlns="http://www.w3.org/2000/svg" width="' + docWidth + '" height="' + docHeight + '">' +
+            '<foreignObject x="0" y="0" width="100%" height="100%">' + xhtml + '</foreignObject></svg>';
+        const img = await loadImage('data:image/svg+xml;charset=utf-8,' + encodeURIComponent(svg));
+
+        const canvas = document.createElement('canvas');
+        canvas.width = Math.round(width * scale);
+        canvas.height = Math.round(height * scale);
+        const ctx = canvas.getContext('2d');
+        // Pages without a background render transparent; use white like a browser
+        ctx.fillStyle = '#ffffff';
+        ctx.fillRect(0, 0, canvas.width, canvas.height);
+        ctx.drawImage(img, x, y, width, height, 0, 0, canvas.width, canvas.height);
+        return canvas;
+    };
+})();
@@ -1,27 +1,254 @@
 //! Native screenshot command using xcap
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
-use image::ImageFormat;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ImageFormat, RgbaImage};
 use std::io::Cursor;
+use std::path::Path;
 use xcap::Window;
 
-/// Get the CGWindowID for the largest visible window belonging to the given PID.
-/// This is used on macOS to capture screenshots using the `screencapture` command.
-pub fn get_window_id_by_pid(pid: u32) -> Result<u32, String> {
-    tracing::debug!("Getting window ID for PID {}", pid);
+/// Default maximum dimensions of encoded screenshots
+const MAX_WIDTH: u32 = 1920;
+const MAX_HEIGHT: u32 = 1080;
+
+/// Maximum height of stitched full-page screenshots
+const FULL_PAGE_MAX_HEIGHT: u32 = 16384;
+
+/// Maximum size of stitched full-page screenshots in pixels (about 128 MB as RGBA)
+const FULL_PAGE_MAX_PIXELS: f64 = 32_000_000.0;
+
+/// JPEG quality used when none is requested
+const DEFAULT_JPEG_QUALITY: u8 = 80;
+
+/// Encoding of screenshot output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    #[serde(alias = "jpg")]
+    Jpeg,
+    Webp,
+}
+
+impl OutputFormat {
+    fn mime_type(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::Webp => "image/webp",
+        }
+    }
+
+    /// Guess the format from a file extension
+    fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(OutputFormat::Png),
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg),
+            "webp" => Some(OutputFormat::Webp),
+            _ => None,
+        }
+    }
+}
+
+/// Output options shared by all screenshot commands
+///
+/// Also passed to the in-page rendering fallback, which applies the same options.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ImageOptions {
+    /// Output encoding (default: from `path` extension, otherwise PNG)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<OutputFormat>,
+    /// JPEG quality, 1-100 (WebP output is always lossless and takes none)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u32>,
+    /// Scale factor applied before the maximum dimensions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+    /// Write the image to this file instead of returning it inline; relative
+    /// paths are resolved against the project root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl ImageOptions {
+    /// Read output options from request params, resolving `path` against
+    /// `project_root`
+    pub fn from_params(params: &serde_json::Value, project_root: &Path) -> Result<Self, String> {
+        let mut options: ImageOptions = if params.is_object() {
+            serde_json::from_value(params.clone())
+                .map_err(|e| format!("Invalid screenshot options: {}", e))?
+        } else {
+            ImageOptions::default()
+        };
+        if options.format.is_none() {
+            options.format = options.path.as_deref().and_then(OutputFormat::from_path);
+        }
+        options.path = options
+            .path
+            .map(|path| project_root.join(path).display().to_string());
+        if let Some(quality) = options.quality {
+            if !(1..=100).contains(&quality) {
+                return Err("quality must be between 1 and 100".to_string());
+            }
+            if options.format == Some(OutputFormat::Webp) {
+                return Err(
+                    "quality is not supported for WebP, which is always lossless".to_string(),
+                );
+            }
+        }
+        if options
+            .scale
+            .is_some_and(|scale| !scale.is_finite() || scale <= 0.0)
+        {
+            return Err("scale must be a positive number".to_string());
+        }
+        if options.max_width == Some(0) || options.max_height == Some(0) {
+            return Err("max_width and max_height must be positive".to_string());
+        }
+        Ok(options)
+    }
+
+    /// Output dimensions for an image, applying the scale and maximum size
+    fn target_dimensions(
+        &self,
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    ) -> (u32, u32) {
+        let scale = self.scale.unwrap_or(1.0);
+        let scaled_width = ((width as f64 * scale).round() as u32).max(1);
+        let scaled_height = ((height as f64 * scale).round() as u32).max(1);
+        resize_dimensions(
+            scaled_width,
+            scaled_height,
+            self.max_width.unwrap_or(max_width),
+            self.max_height.unwrap_or(max_height),
+        )
+    }
+}
+
+/// Describes a Tauri window so it can be matched against the native windows of the process
+#[derive(Debug, Clone, Default)]
+pub struct WindowMatcher {
+    /// Window title
+    pub title: String,
+    /// Outer position in physical pixels
+    pub x: i32,
+    pub y: i32,
+    /// Outer size in physical pixels
+    pub width: u32,
+    pub height: u32,
+    /// Scale factor, used when the platform reports logical bounds (macOS)
+    pub scale_factor: f64,
+    /// Native handle, where it equals the xcap window id (HWND on Windows)
+    pub native_id: Option<u32>,
+}
+
+impl WindowMatcher {
+    /// Distance between the expected bounds and a native window's bounds.
+    /// Both physical and logical interpretations are tried; the closer one wins.
+    fn distance(&self, window: &Window) -> u64 {
+        let native = (
+            window.x().unwrap_or(0) as f64,
+            window.y().unwrap_or(0) as f64,
+            window.width().unwrap_or(0) as f64,
+            window.height().unwrap_or(0) as f64,
+        );
+        let physical = (
+            self.x as f64,
+            self.y as f64,
+            self.width as f64,
+            self.height as f64,
+        );
+        let scale = if self.scale_factor > 0.0 {
+            self.scale_factor
+        } else {
+            1.0
+        };
+        let logical = (
+            physical.0 / scale,
+            physical.1 / scale,
+            physical.2 / scale,
+            physical.3 / scale,
+        );
+        let diff = |expected: (f64, f64, f64, f64)| {
+            (native.0 - expected.0).abs()
+                + (native.1 - expected.1).abs()
+                + (native.2 - expected.2).abs()
+                + (native.3 - expected.3).abs()
+        };
+        diff(physical).min(diff(logical)) as u64
+    }
+}
+
+/// Find a visible window belonging to the given PID.
+///
+/// With a matcher, the native handle is tried first, then windows with the same
+/// title, picking the one whose bounds are closest. No other window is ever
+/// substituted: capturing the wrong window would look like success. Without a
+/// matcher, the largest visible window is used.
+fn find_window(pid: u32, matcher: Option<&WindowMatcher>) -> Result<Window, String> {
+    tracing::debug!("Enumerating windows for PID {}", pid);
     let windows = Window::all().map_err(|e| format!("Failed to enumerate windows: {}", e))?;
+    tracing::debug!("Found {} total windows", windows.len());
 
-    let target = windows
+    // Find windows matching the PID, filter out minimized ones
+    let candidates: Vec<_> = windows
         .into_iter()
         .filter(|w| w.current_monitor().is_ok())
         .filter(|w| w.pid().map(|p| p == pid).unwrap_or(false))
         .filter(|w| !w.is_minimized().unwrap_or(true))
-        .max_by_key(|w| {
+        .collect();
+
+    tracing::debug!(
+        "Found {} windows matching PID {} (not minimized)",
+        candidates.len(),
+        pid
+    );
+
+    let target = match matcher {
+        Some(matcher) => {
+            let by_handle = matcher
+                .native_id
+                .and_then(|id| candidates.iter().find(|w| w.id().ok() == Some(id)));
+            by_handle.cloned().or_else(|| {
+                candidates
+                    .iter()
+                    .filter(|w| w.title().map(|t| t == matcher.title).unwrap_or(false))
+                    .min_by_key(|w| matcher.distance(w))
+                    .cloned()
+            })
+        }
+        None => candidates.into_iter().max_by_key(|w| {
             let width = w.width().unwrap_or(0);
             let height = w.height().unwrap_or(0);
             width * height
-        })
-        .ok_or_else(|| format!("No visible window found for PID {}", pid))?;
+        }),
+    };
+
+    target.ok_or_else(|| match matcher {
+        Some(m) => format!(
+            "No visible window titled {:?} found for PID {}",
+            m.title, pid
+        ),
+        None => format!("No visible window found for PID {}", pid),
+    })
+}
+
+/// Get the CGWindowID of a visible window belonging to the given PID.
+/// This is used on macOS to capture screenshots using the `screencapture` command.
+pub fn get_window_id_by_pid(pid: u32, matcher: Option<&WindowMatcher>) -> Result<u32, String> {
+    tracing::debug!("Getting window ID for PID {}", pid);
+    let target = find_window(pid, matcher)?;
 
     let window_id = target
         .id()
@@ -54,10 +281,293 @@ fn check_screen_recording_permission() -> bool {
     true // No permission check needed on other platforms
 }
 
-/// Capture window by process ID
+/// A rectangle, in whatever unit the context states (CSS or image pixels)
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Physical-pixel layout of a webview inside its window, used to locate page
+/// content in native window captures
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WebviewGeometry {
+    /// Window outer size, including decorations
+    pub outer_width: u32,
+    pub outer_height: u32,
+    /// Offset of the client area from the window's outer top-left corner
+    pub client_x: i32,
+    pub client_y: i32,
+    /// Client area size
+    pub inner_width: u32,
+    pub inner_height: u32,
+    /// Webview position inside the client area
+    pub webview_x: i32,
+    pub webview_y: i32,
+}
+
+/// Maps CSS pixels of a page to pixels of a captured window image
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct ImageMapping {
+    /// Physical pixels per CSS pixel (`window.devicePixelRatio`)
+    pub device_pixel_ratio: f64,
+    /// Image pixels per physical pixel
+    pub image_scale: f64,
+    /// Position of the webview's top-left corner in image pixels
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+impl ImageMapping {
+    /// Derive the mapping for a raw capture of `image_width`x`image_height`.
+    ///
+    /// Some platforms capture the window with its decorations and others only
+    /// the client area; whichever size predicts the image height better wins.
+    pub fn new(
+        geometry: &WebviewGeometry,
+        device_pixel_ratio: f64,
+        image_width: u32,
+        image_height: u32,
+    ) -> Self {
+        let image_width = image_width as f64;
+        let image_height = image_height as f64;
+        let outer_scale = image_width / geometry.outer_width.max(1) as f64;
+        let inner_scale = image_width / geometry.inner_width.max(1) as f64;
+        let outer_error = (geometry.outer_height as f64 * outer_scale - image_height).abs();
+        let inner_error = (geometry.inner_height as f64 * inner_scale - image_height).abs();
+
+        let (image_scale, origin_x, origin_y) = if outer_error < inner_error {
+            (
+                outer_scale,
+                (geometry.client_x + geometry.webview_x) as f64,
+                (geometry.client_y + geometry.webview_y) as f64,
+            )
+        } else {
+            (
+                inner_scale,
+                geometry.webview_x as f64,
+                geometry.webview_y as f64,
+            )
+        };
+
+        Self {
+            device_pixel_ratio,
+            image_scale,
+            offset_x: origin_x * image_scale,
+            offset_y: origin_y * image_scale,
+        }
+    }
+
+    /// Convert a viewport-relative rectangle in CSS pixels to image pixels
+    pub fn css_to_image(&self, rect: &Rect) -> Rect {
+        let factor = self.device_pixel_ratio * self.image_scale;
+        Rect {
+            x: self.offset_x + rect.x * factor,
+            y: self.offset_y + rect.y * factor,
+            width: rect.width * factor,
+            height: rect.height * factor,
+        }
+    }
+
+    /// The equivalent transform for the unresized capture
+    pub fn transform(&self) -> ImageTransform {
+        ImageTransform {
+            scale: self.device_pixel_ratio * self.image_scale,
+            offset_x: self.offset_x,
+            offset_y: self.offset_y,
+            device_pixel_ratio: self.device_pixel_ratio,
+            resize_ratio: 1.0,
+            webview_x: Some(self.offset_x),
+            webview_y: Some(self.offset_y),
+        }
+    }
+}
+
+/// Transform from viewport CSS pixels to pixels of a returned screenshot
 ///
-/// Finds the largest visible window belonging to the given PID and captures it.
-pub fn capture_window_by_pid(pid: u32) -> Result<serde_json::Value, String> {
+/// `image = offset + css * scale`, with viewport coordinates as they were when
+/// the screenshot was taken. Returned with every screenshot as `transform`.
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+pub struct ImageTransform {
+    /// Image pixels per CSS pixel
+    pub scale: f64,
+    /// Position of the viewport's top-left corner in image pixels
+    pub offset_x: f64,
+    pub offset_y: f64,
+    /// Physical pixels per CSS pixel (`window.devicePixelRatio`)
+    pub device_pixel_ratio: f64,
+    /// Image pixels per captured pixel, below 1 when the capture was downscaled
+    pub resize_ratio: f64,
+    /// Webview's top-left corner inside a window capture, in captured pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webview_x: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webview_y: Option<f64>,
+}
+
+impl ImageTransform {
+    /// Transform for a capture whose top-left corner shows viewport point
+    /// (`origin_x`, `origin_y`), at `scale` image pixels per CSS pixel
+    pub fn region(origin_x: f64, origin_y: f64, scale: f64, device_pixel_ratio: f64) -> Self {
+        Self {
+            scale,
+            offset_x: -origin_x * scale,
+            offset_y: -origin_y * scale,
+            device_pixel_ratio,
+            resize_ratio: 1.0,
+            webview_x: None,
+            webview_y: None,
+        }
+    }
+
+    /// Account for the capture being resized from `source_width` to `width` pixels
+    pub fn resized(self, source_width: u32, width: u32) -> Self {
+        let ratio = width as f64 / source_width.max(1) as f64;
+        Self {
+            scale: self.scale * ratio,
+            offset_x: self.offset_x * ratio,
+            offset_y: self.offset_y * ratio,
+            resize_ratio: self.resize_ratio * ratio,
+            ..self
+        }
+    }
+
+    /// Convert a viewport-relative rectangle in CSS pixels to image pixels
+    pub fn css_to_image(&self, rect: &Rect) -> Rect {
+        Rect {
+            x: self.offset_x + rect.x * self.scale,
+            y: self.offset_y + rect.y * self.scale,
+            width: rect.width * self.scale,
+            height: rect.height * self.scale,
+        }
+    }
+
+    /// Convert an image point to a viewport point in CSS pixels
+    pub fn image_to_css(&self, x: f64, y: f64) -> (f64, f64) {
+        let scale = if self.scale > 0.0 { self.scale } else { 1.0 };
+        ((x - self.offset_x) / scale, (y - self.offset_y) / scale)
+    }
+}
+
+/// An element to mask: a snapshot ref or a locator (all matches)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum MaskTarget {
+    Ref(u32),
+    Selector(String),
+}
+
+/// Options preparing the page for a deterministic capture
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct CaptureSetup {
+    /// Elements painted over with `mask_color` in the captured image
+    pub mask: Vec<MaskTarget>,
+    /// Mask color as `#RRGGBB` (default: magenta)
+    pub mask_color: Option<String>,
+    /// Pause CSS transitions and animations while capturing
+    pub disable_animations: bool,
+}
+
+impl CaptureSetup {
+    /// Read capture setup options from request params
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        let setup: CaptureSetup = if params.is_object() {
+            serde_json::from_value(params.clone())
+                .map_err(|e| format!("Invalid capture options: {}", e))?
+        } else {
+            CaptureSetup::default()
+        };
+        setup.color()?;
+        Ok(setup)
+    }
+
+    /// Whether the page must be prepared before capturing
+    /// Plain captures stay purely native, so they work in hidden or hung pages
+    pub fn needs_page(&self) -> bool {
+        self.disable_animations || !self.mask.is_empty()
+    }
+
+    /// Parsed mask color
+    pub fn color(&self) -> Result<[u8; 4], String> {
+        let Some(color) = &self.mask_color else {
+            return Ok([255, 0, 255, 255]);
+        };
+        let hex = color.trim_start_matches('#');
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("Invalid mask_color '{}': expected #RRGGBB", color))
+        };
+        if hex.len() != 6 {
+            return Err(format!("Invalid mask_color '{}': expected #RRGGBB", color));
+        }
+        Ok([channel(0)?, channel(2)?, channel(4)?, 255])
+    }
+}
+
+/// Resolved mask rectangles (viewport CSS pixels) and how to paint them
+#[derive(Debug, Clone, Default)]
+pub struct Masks {
+    pub rects: Vec<Rect>,
+    pub color: [u8; 4],
+    pub geometry: WebviewGeometry,
+    pub device_pixel_ratio: f64,
+}
+
+impl Masks {
+    /// Paint the masks onto a raw capture of the whole window
+    pub fn paint_window(&self, image: &mut RgbaImage) {
+        if self.rects.is_empty() {
+            return;
+        }
+        let mapping = ImageMapping::new(
+            &self.geometry,
+            self.device_pixel_ratio,
+            image.width(),
+            image.height(),
+        );
+        for rect in &self.rects {
+            self.fill(image, &mapping.css_to_image(rect));
+        }
+    }
+
+    /// Paint the masks onto a capture cropped to `region` (viewport CSS pixels)
+    ///
+    /// `scale` is the number of image pixels per CSS pixel.
+    pub fn paint_region(&self, image: &mut RgbaImage, region: &Rect, scale: f64) {
+        for rect in &self.rects {
+            let target = Rect {
+                x: (rect.x - region.x) * scale,
+                y: (rect.y - region.y) * scale,
+                width: rect.width * scale,
+                height: rect.height * scale,
+            };
+            self.fill(image, &target);
+        }
+    }
+
+    /// Fill a rectangle in image pixels, clamped to the image
+    fn fill(&self, image: &mut RgbaImage, rect: &Rect) {
+        let left = rect.x.max(0.0).floor() as u32;
+        let top = rect.y.max(0.0).floor() as u32;
+        let right = ((rect.x + rect.width).ceil().max(0.0) as u32).min(image.width());
+        let bottom = ((rect.y + rect.height).ceil().max(0.0) as u32).min(image.height());
+        for y in top..bottom {
+            for x in left..right {
+                image.put_pixel(x, y, image::Rgba(self.color));
+            }
+        }
+    }
+}
+
+/// Capture the raw image of the matching window for the given PID
+pub fn capture_image_by_pid(
+    pid: u32,
+    matcher: Option<&WindowMatcher>,
+) -> Result<RgbaImage, String> {
     // Check Screen Recording permission on macOS
     if !check_screen_recording_permission() {
         return Err(
@@ -68,32 +578,7 @@ pub fn capture_window_by_pid(pid: u32) -> Result<serde_json::Value, String> {
         );
     }
 
-    tracing::debug!("Enumerating windows for PID {}", pid);
-    let windows = Window::all().map_err(|e| format!("Failed to enumerate windows: {}", e))?;
-    tracing::debug!("Found {} total windows", windows.len());
-
-    // Find windows matching the PID, filter out minimized ones, pick the largest
-    let matching_windows: Vec<_> = windows
-        .into_iter()
-        .filter(|w| w.current_monitor().is_ok())
-        .filter(|w| w.pid().map(|p| p == pid).unwrap_or(false))
-        .filter(|w| !w.is_minimized().unwrap_or(true))
-        .collect();
-
-    tracing::debug!(
-        "Found {} windows matching PID {} (not minimized)",
-        matching_windows.len(),
-        pid
-    );
-
-    let target = matching_windows
-        .into_iter()
-        .max_by_key(|w| {
-            let width = w.width().unwrap_or(0);
-            let height = w.height().unwrap_or(0);
-            width * height
-        })
-        .ok_or_else(|| format!("No visible window found for PID {}", pid))?;
+    let target = find_window(pid, matcher)?;
 
     tracing::debug!(
         "Capturing window: {:?} ({}x{})",
@@ -102,21 +587,186 @@ pub fn capture_window_by_pid(pid: u32) -> Result<serde_json::Value, String> {
         target.height().unwrap_or(0)
     );
 
-    capture_xcap_window(&target)
+    target
+        .capture_image()
+        .map_err(|e| format!("Failed to capture window: {}", e))
 }
 
-/// Capture a specific xcap Window and return as base64 PNG
-fn capture_xcap_window(window: &Window) -> Result<serde_json::Value, String> {
-    // Capture the window image
-    let rgba_image = window
-        .capture_image()
-        .map_err(|e| format!("Failed to capture window: {}", e))?;
+/// Capture window by process ID
+///
+/// Captures the window described by `matcher`, or the largest visible window
+/// belonging to the given PID when no matcher is given.
+pub fn capture_window_by_pid(
+    pid: u32,
+    matcher: Option<&WindowMatcher>,
+    options: &ImageOptions,
+    masks: &Masks,
+) -> Result<serde_json::Value, String> {
+    let mut rgba_image = capture_image_by_pid(pid, matcher)?;
+    masks.paint_window(&mut rgba_image);
+    let mapping = ImageMapping::new(
+        &masks.geometry,
+        masks.device_pixel_ratio,
+        rgba_image.width(),
+        rgba_image.height(),
+    );
+    encode_capture(
+        rgba_image,
+        options,
+        MAX_WIDTH,
+        MAX_HEIGHT,
+        mapping.transform(),
+    )
+}
+
+/// Capture a viewport region of a webview by cropping the native window capture
+///
+/// `rect` is viewport-relative in CSS pixels. Returns the cropped image and the
+/// number of image pixels per CSS pixel.
+pub fn capture_region_by_pid(
+    pid: u32,
+    matcher: &WindowMatcher,
+    geometry: &WebviewGeometry,
+    rect: &Rect,
+    device_pixel_ratio: f64,
+) -> Result<(RgbaImage, f64), String> {
+    let rgba_image = capture_image_by_pid(pid, Some(matcher))?;
+    let mapping = ImageMapping::new(
+        geometry,
+        device_pixel_ratio,
+        rgba_image.width(),
+        rgba_image.height(),
+    );
+    let region = mapping.css_to_image(rect);
+
+    // Clamp the region to the captured image
+    let left = region.x.max(0.0).floor() as u32;
+    let top = region.y.max(0.0).floor() as u32;
+    let right = ((region.x + region.width).ceil().max(0.0) as u32).min(rgba_image.width());
+    let bottom = ((region.y + region.height).ceil().max(0.0) as u32).min(rgba_image.height());
+    if right <= left || bottom <= top {
+        return Err("Region is outside the captured window area".to_string());
+    }
+
+    let cropped =
+        image::imageops::crop_imm(&rgba_image, left, top, right - left, bottom - top).to_image();
+    Ok((cropped, device_pixel_ratio * mapping.image_scale))
+}
+
+/// Capture a single element by cropping the native window capture
+///
+/// `rect` is the element's viewport-relative bounding box in CSS pixels.
+pub fn capture_element_by_pid(
+    pid: u32,
+    matcher: &WindowMatcher,
+    geometry: &WebviewGeometry,
+    rect: &Rect,
+    device_pixel_ratio: f64,
+    options: &ImageOptions,
+    masks: &Masks,
+) -> Result<serde_json::Value, String> {
+    let (mut cropped, scale) =
+        capture_region_by_pid(pid, matcher, geometry, rect, device_pixel_ratio)?;
+    masks.paint_region(&mut cropped, rect, scale);
+    let transform = ImageTransform::region(rect.x, rect.y, scale, device_pixel_ratio);
+    encode_capture(cropped, options, MAX_WIDTH, MAX_HEIGHT, transform)
+}
+
+/// Factor to resize full-page frames by before stitching
+///
+/// `width` is the frame width and `height` the full page height, both in
+/// captured pixels. Frames are brought to the output size up front (scale and
+/// maximum dimensions), and further down if the stitched page would exceed
+/// [`FULL_PAGE_MAX_PIXELS`], so memory stays bounded however tall the page is.
+pub fn full_page_frame_factor(width: u32, height: f64, options: &ImageOptions) -> f64 {
+    let height = height.max(1.0);
+    let (target_width, target_height) = options.target_dimensions(
+        width,
+        height.round() as u32,
+        MAX_WIDTH,
+        FULL_PAGE_MAX_HEIGHT,
+    );
+    let fit = (target_width as f64 / width as f64).min(target_height as f64 / height);
+    let pixels = width as f64 * fit * height * fit;
+    fit * (FULL_PAGE_MAX_PIXELS / pixels).sqrt().min(1.0)
+}
+
+/// Resize a full-page frame by the factor from [`full_page_frame_factor`]
+pub fn resize_frame(frame: RgbaImage, factor: f64) -> RgbaImage {
+    if factor == 1.0 {
+        return frame;
+    }
+    let width = ((frame.width() as f64 * factor).round() as u32).max(1);
+    let height = ((frame.height() as f64 * factor).round() as u32).max(1);
+    image::imageops::resize(&frame, width, height, image::imageops::FilterType::Triangle)
+}
+
+/// Stitch frames captured while scrolling into one full-page image
+///
+/// Each frame is paired with the scroll offset (CSS pixels) it was captured
+/// at and `scroll_height` is the total scrollable height in CSS pixels.
+/// `transform` maps the viewport onto the stitched image; its scale places
+/// the frames. Frames are expected at output size already (see
+/// [`resize_frame`]), so the options' `scale` is not applied again.
+pub fn stitch_full_page(
+    frames: Vec<(f64, RgbaImage)>,
+    scroll_height: f64,
+    options: &ImageOptions,
+    transform: ImageTransform,
+) -> Result<serde_json::Value, String> {
+    let scale = transform.scale;
+    let width = frames
+        .first()
+        .map(|(_, frame)| frame.width())
+        .ok_or_else(|| "No frames captured".to_string())?;
+    let height = (scroll_height * scale).round().max(1.0) as u32;
 
+    let mut canvas = RgbaImage::new(width, height);
+    for (offset, frame) in &frames {
+        let y = (offset * scale).round() as i64;
+        image::imageops::replace(&mut canvas, frame, 0, y);
+    }
+
+    let options = ImageOptions {
+        scale: None,
+        ..options.clone()
+    };
+    let mut result = encode_capture(canvas, &options, MAX_WIDTH, FULL_PAGE_MAX_HEIGHT, transform)?;
+    result["frames"] = serde_json::json!(frames.len());
+    Ok(result)
+}
+
+/// Encode a capture with [`encode_image`], adding its `transform` adjusted
+/// for any resizing
+fn encode_capture(
+    rgba_image: RgbaImage,
+    options: &ImageOptions,
+    max_width: u32,
+    max_height: u32,
+    transform: ImageTransform,
+) -> Result<serde_json::Value, String> {
+    let source_width = rgba_image.width();
+    let mut result = encode_image(rgba_image, options, max_width, max_height)?;
+    let width = result["width"].as_u64().unwrap_or(source_width as u64) as u32;
+    result["transform"] = serde_json::json!(transform.resized(source_width, width));
+    Ok(result)
+}
+
+/// Resize and encode a captured image as a base64 data URL, or straight to
+/// the options' `path` (see [`write_image`])
+///
+/// `max_width`/`max_height` apply unless the options override them.
+pub fn encode_image(
+    rgba_image: RgbaImage,
+    options: &ImageOptions,
+    max_width: u32,
+    max_height: u32,
+) -> Result<serde_json::Value, String> {
     let orig_width = rgba_image.width();
     let orig_height = rgba_image.height();
 
-    // Resize if larger than 1920x1080
-    let (width, height) = resize_dimensions(orig_width, orig_height, 1920, 1080);
+    // Resize if scaled or larger than the allowed dimensions
+    let (width, height) = options.target_dimensions(orig_width, orig_height, max_width, max_height);
     let final_image = if width != orig_width || height != orig_height {
         image::imageops::resize(
             &rgba_image,
@@ -128,25 +778,91 @@ fn capture_xcap_window(window: &Window) -> Result<serde_json::Value, String> {
         rgba_image
     };
 
-    // Encode to PNG
+    // Encode in the requested format
+    let format = options.format.unwrap_or_default();
     let mut buffer = Cursor::new(Vec::new());
-    image::DynamicImage::ImageRgba8(final_image)
-        .write_to(&mut buffer, ImageFormat::Png)
-        .map_err(|e| format!("Failed to encode image: {}", e))?;
-
-    // Base64 encode
-    let base64_data = BASE64.encode(buffer.into_inner());
-    let data_url = format!("data:image/png;base64,{}", base64_data);
+    let encoded = match format {
+        OutputFormat::Png => {
+            DynamicImage::ImageRgba8(final_image).write_to(&mut buffer, ImageFormat::Png)
+        }
+        OutputFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let quality = options.quality.unwrap_or(DEFAULT_JPEG_QUALITY);
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(final_image).to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, quality))
+        }
+        OutputFormat::Webp => DynamicImage::ImageRgba8(final_image)
+            .write_with_encoder(WebPEncoder::new_lossless(&mut buffer)),
+    };
+    encoded.map_err(|e| format!("Failed to encode image: {}", e))?;
 
-    Ok(serde_json::json!({
-        "data": data_url,
+    let bytes = buffer.into_inner();
+    let mut result = serde_json::json!({
         "width": width,
         "height": height
-    }))
+    });
+    match &options.path {
+        Some(path) => write_image(&mut result, Path::new(path), &bytes, format.mime_type())?,
+        None => {
+            let base64_data = BASE64.encode(bytes);
+            result["data"] = serde_json::json!(format!(
+                "data:{};base64,{}",
+                format.mime_type(),
+                base64_data
+            ));
+        }
+    }
+    Ok(result)
+}
+
+/// Write an in-page rendered screenshot result to `path`, replacing its
+/// data URL with the path
+///
+/// Native captures are written by [`encode_image`] directly.
+pub fn save_image(result: &mut serde_json::Value, path: &str) -> Result<(), String> {
+    let data_url = result["data"]
+        .as_str()
+        .ok_or_else(|| "Screenshot result has no image data".to_string())?;
+    let (header, base64_data) = data_url
+        .split_once(";base64,")
+        .ok_or_else(|| "Screenshot data is not a base64 data URL".to_string())?;
+    let mime_type = header.trim_start_matches("data:").to_string();
+    let bytes = BASE64
+        .decode(base64_data)
+        .map_err(|e| format!("Failed to decode image data: {}", e))?;
+    write_image(result, Path::new(path), &bytes, &mime_type)
+}
+
+/// Write encoded image bytes to `path`, creating its directory, and record
+/// `path`, `mimeType` and `bytes` in the result in place of `data`
+fn write_image(
+    result: &mut serde_json::Value,
+    path: &Path,
+    bytes: &[u8],
+    mime_type: &str,
+) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, bytes)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let object = result
+        .as_object_mut()
+        .ok_or_else(|| "Screenshot result is not an object".to_string())?;
+    object.remove("data");
+    object.insert(
+        "path".to_string(),
+        serde_json::json!(path.display().to_string()),
+    );
+    object.insert("mimeType".to_string(), serde_json::json!(mime_type));
+    object.insert("bytes".to_string(), serde_json::json!(bytes.len()));
+    Ok(())
 }
 
 /// Calculate resized dimensions maintaining aspect ratio
-fn resize_dimensions(w: u32, h: u32, max_w: u32, max_h: u32) -> (u32, u32) {
+pub fn resize_dimensions(w: u32, h: u32, max_w: u32, max_h: u32) -> (u32, u32) {
     if w <= max_w && h <= max_h {
         return (w, h);
     }
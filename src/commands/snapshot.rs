@@ -1 +1,486 @@
 //! DOM snapshot command
+
+/// How `snapshot` returns the tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    /// Indented outline, one line per node
+    #[default]
+    Text,
+    /// Nested node objects
+    Json,
+}
+
+/// The element a scoped snapshot starts from: a snapshot ref or a locator
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum SnapshotRoot {
+    Ref(u32),
+    Selector(String),
+}
+
+/// Options for `snapshot`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SnapshotOptions {
+    pub format: SnapshotFormat,
+    /// Snapshot this element's subtree instead of the whole body
+    pub root: Option<SnapshotRoot>,
+    /// Levels of the tree to return; deeper nodes are counted on their ancestor
+    pub max_depth: Option<u32>,
+    /// Nodes (or added and changed nodes of a diff) to return before truncating
+    pub max_nodes: usize,
+    /// Continue a truncated snapshot from the cursor it returned
+    pub cursor: Option<String>,
+    /// Only interactive elements, hoisting them out of their containers
+    pub interactive_only: bool,
+    /// Add each node's bounding box (viewport CSS pixels)
+    pub include_bounds: bool,
+    /// Report only what changed since this snapshot id
+    pub since: Option<String>,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            format: SnapshotFormat::Text,
+            root: None,
+            max_depth: None,
+            max_nodes: 500,
+            cursor: None,
+            interactive_only: false,
+            include_bounds: false,
+            since: None,
+        }
+    }
+}
+
+impl SnapshotOptions {
+    /// Read snapshot options from request params
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        let options: SnapshotOptions = serde_json::from_value(params.clone())
+            .map_err(|e| format!("Invalid snapshot options: {}", e))?;
+        if options.max_nodes == 0 {
+            return Err("max_nodes must be at least 1".to_string());
+        }
+        if options.max_depth == Some(0) {
+            return Err("max_depth must be at least 1".to_string());
+        }
+        if options.cursor.is_some() && options.since.is_some() {
+            return Err(
+                "cursor continues an earlier snapshot and cannot be combined with since"
+                    .to_string(),
+            );
+        }
+        Ok(options)
+    }
+}
+
+/// JavaScript code to build an accessibility tree snapshot
+///
+/// Every snapshot gets a `snapshotId`. The tree comes as text in `snapshot`,
+/// or as nodes in `tree` for [`SnapshotFormat::Json`]. Past `max_nodes` it is
+/// cut, with `truncated` carrying the cursor that continues it.
+///
+/// With `since`, the result carries only the added, removed and changed nodes
+/// relative to that snapshot, structured in `diff` (and as text in `snapshot`);
+/// when that snapshot is gone (reload, or too old) the full tree is returned
+/// with a `note`. Diffs compare whatever both snapshots covered, so use the
+/// same `root` and filters for both. Added and changed nodes past `max_nodes`
+/// are cut too, with `truncated` counting the rest (diffs have no cursor).
+pub fn snapshot_js(options: &SnapshotOptions) -> String {
+    format!(
+        "{}\nconst options = {};\n{}",
+        super::REQUIRE_LOCATOR_JS,
+        serde_json::to_string(options).unwrap(),
+        SNAPSHOT_JS
+    )
+}
+
+/// JavaScript code to build accessibility tree snapshot, run after `options` is defined
+/// Note: This code is wrapped by eval_with_result, so it should end with a return statement
+const SNAPSHOT_JS: &str = r#"
+    // Refs are kept by the bridge, so an element keeps its ref across snapshots
+    const { getRole, getAccessibleName, assignRef, pruneRefs, resolveRef, strict } = window.__MCP_LOCATOR__;
+    pruneRefs();
+
+    function isVisible(el) {
+        if (!el.offsetParent && el.tagName.toLowerCase() !== 'body') return false;
+        const style = window.getComputedStyle(el);
+        if (style.display === 'none' || style.visibility === 'hidden' || style.opacity === '0') return false;
+        return true;
+    }
+
+    function isInteractive(el) {
+        const tag = el.tagName.toLowerCase();
+        const interactiveTags = ['a', 'button', 'input', 'select', 'textarea'];
+        if (interactiveTags.includes(tag)) return true;
+        if (el.getAttribute('role') === 'button') return true;
+        if (el.onclick || el.getAttribute('onclick')) return true;
+        if (el.tabIndex >= 0) return true;
+        return false;
+    }
+
+    function buildTree(el, depth = 0) {
+        if (!el || el.nodeType !== Node.ELEMENT_NODE) return null;
+        if (!isVisible(el)) return null;
+
+        const role = getRole(el);
+        const name = getAccessibleName(el);
+        const isInter = isInteractive(el);
+
+        // Skip non-semantic elements unless they have children worth showing
+        const skipTags = ['div', 'span', 'script', 'style', 'noscript', 'svg', 'path'];
+        const tag = el.tagName.toLowerCase();
+
+        let children = [];
+        for (const child of el.children) {
+            const childNode = buildTree(child, depth + 1);
+            if (Array.isArray(childNode)) children.push(...childNode);
+            else if (childNode) children.push(childNode);
+        }
+
+        // Interactive only: other elements are replaced by their children
+        if (options.interactive_only && !isInter) {
+            return children.length === 1 ? children[0] : children;
+        }
+
+        // Skip non-semantic containers with single child (flatten)
+        if (skipTags.includes(tag) && !role && !isInter && children.length === 1) {
+            return children[0];
+        }
+
+        // Skip empty non-semantic elements
+        if (skipTags.includes(tag) && !role && !isInter && children.length === 0 && !name) {
+            return null;
+        }
+
+        const ref = assignRef(el);
+
+        const node = { ref };
+        if (role) node.role = role;
+        else node.tag = tag;
+        if (name) node.name = name;
+        if (isInter) node.interactive = true;
+
+        // Add value for form elements
+        if (el.value !== undefined && el.value !== '') {
+            node.value = el.value;
+        }
+
+        // Add checked state
+        if (el.checked !== undefined) {
+            node.checked = el.checked;
+        }
+
+        // Add disabled state
+        if (el.disabled) {
+            node.disabled = true;
+        }
+
+        // Add bounding box (viewport CSS pixels)
+        if (options.include_bounds) {
+            const r = el.getBoundingClientRect();
+            node.bounds = { x: Math.round(r.left), y: Math.round(r.top), width: Math.round(r.width), height: Math.round(r.height) };
+        }
+
+        if (children.length > 0) {
+            node.children = children;
+        }
+
+        return node;
+    }
+
+    // Tree nodes in document order, with their parent ref and depth
+    function preorder(roots) {
+        const entries = [];
+        const visit = (node, parent, depth) => {
+            entries.push({ node, parent, depth });
+            for (const child of node.children || []) {
+                visit(child, node.ref, depth + 1);
+            }
+        };
+        for (const root of roots) {
+            visit(root, null, 0);
+        }
+        return entries;
+    }
+
+    // Cuts the tree below max_depth, counting the cut nodes on their ancestor
+    function limitDepth(node, depth) {
+        if (!node.children) return;
+        if (depth >= options.max_depth) {
+            node.omitted = preorder(node.children).length;
+            delete node.children;
+            return;
+        }
+        for (const child of node.children) {
+            limitDepth(child, depth + 1);
+        }
+    }
+
+    function entriesToText(entries) {
+        let result = '';
+        for (const { node, depth } of entries) {
+            let line = '  '.repeat(depth) + `- [ref=${node.ref}]`;
+
+            if (node.role) line += ` ${node.role}`;
+            else if (node.tag) line += ` <${node.tag}>`;
+
+            if (node.name) line += ` "${node.name}"`;
+            if (node.value) line += ` value="${node.value}"`;
+            if (node.checked) line += ` [checked]`;
+            if (node.disabled) line += ` [disabled]`;
+            if (node.bounds) {
+                const { x, y, width, height } = node.bounds;
+                line += ` @${x},${y} ${width}x${height}`;
+            }
+            if (node.omitted) line += ` (+${node.omitted} below max_depth)`;
+
+            result += line + '\n';
+        }
+        return result;
+    }
+
+    // Nested copies of a slice of preorder() entries; an entry whose parent is
+    // outside the slice becomes a root carrying its `parent` ref
+    function entriesToTree(entries) {
+        const roots = [];
+        const copies = new Map();
+        for (const { node, parent } of entries) {
+            const { children, ...copy } = node;
+            copies.set(node.ref, copy);
+            const parentCopy = copies.get(parent);
+            if (parentCopy) {
+                parentCopy.children = parentCopy.children || [];
+                parentCopy.children.push(copy);
+            } else {
+                if (parent !== null) copy.parent = parent;
+                roots.push(copy);
+            }
+        }
+        return roots;
+    }
+
+    // Flatten the tree into ref -> node state (with the parent ref), in tree order
+    function flatten(entries) {
+        const nodes = new Map();
+        for (const { node, parent } of entries) {
+            const { children, ...state } = node;
+            nodes.set(node.ref, { ...state, parent });
+        }
+        return nodes;
+    }
+
+    const DIFF_FIELDS = ['role', 'tag', 'name', 'value', 'checked', 'disabled', 'interactive', 'parent'];
+    const FLAGS = ['checked', 'disabled', 'interactive'];
+
+    // Missing flags are false, other missing fields null
+    const fieldOf = (node, field) => node[field] ?? (FLAGS.includes(field) ? false : null);
+
+    function diffTrees(before, after) {
+        const added = [];
+        const removed = [];
+        const changed = [];
+        for (const [ref, node] of after) {
+            const old = before.get(ref);
+            if (!old) {
+                added.push(node);
+                continue;
+            }
+            const changes = {};
+            for (const field of DIFF_FIELDS) {
+                const from = fieldOf(old, field);
+                const to = fieldOf(node, field);
+                if (from !== to) {
+                    changes[field] = { from, to };
+                }
+            }
+            if (Object.keys(changes).length > 0) {
+                changed.push({ ref, changes });
+            }
+        }
+        for (const ref of before.keys()) {
+            if (!after.has(ref)) removed.push(ref);
+        }
+        return { added, removed, changed };
+    }
+
+    function nodeLabel(node) {
+        let label = `[ref=${node.ref}]`;
+        if (node.role) label += ` ${node.role}`;
+        else if (node.tag) label += ` <${node.tag}>`;
+        if (node.name) label += ` "${node.name}"`;
+        return label;
+    }
+
+    // One line per change: + added (nested under added parents), - removed
+    // (descendants folded into a count), ~ changed fields
+    function diffToText(diff, before, after) {
+        let result = '';
+
+        const depths = new Map();
+        for (const node of diff.added) {
+            const nested = depths.has(node.parent);
+            const depth = nested ? depths.get(node.parent) + 1 : 0;
+            depths.set(node.ref, depth);
+            let line = '+ ' + '  '.repeat(depth) + nodeLabel(node);
+            if (node.value) line += ` value="${node.value}"`;
+            if (node.checked) line += ` [checked]`;
+            if (node.disabled) line += ` [disabled]`;
+            if (!nested && node.parent !== null) line += ` (in ref=${node.parent})`;
+            result += line + '\n';
+        }
+
+        const removedRefs = new Set(diff.removed);
+        const descendants = new Map();
+        for (const ref of diff.removed) {
+            let top = ref;
+            while (removedRefs.has(before.get(top).parent)) {
+                top = before.get(top).parent;
+            }
+            if (top !== ref) descendants.set(top, (descendants.get(top) || 0) + 1);
+        }
+        for (const ref of diff.removed) {
+            if (removedRefs.has(before.get(ref).parent)) continue;
+            let line = '- ' + nodeLabel(before.get(ref));
+            if (descendants.has(ref)) line += ` (and ${descendants.get(ref)} descendants)`;
+            result += line + '\n';
+        }
+
+        const show = (value) => (typeof value === 'string' ? `"${value}"` : String(value));
+        for (const { ref, changes } of diff.changed) {
+            const fields = Object.entries(changes).map(([field, { from, to }]) => (
+                field === 'parent' ? `moved from ref=${from} to ref=${to}` : `${field} ${show(from)} -> ${show(to)}`
+            ));
+            result += '~ ' + nodeLabel(after.get(ref)) + ': ' + fields.join(', ') + '\n';
+        }
+
+        return result;
+    }
+
+    // Recent trees are kept for `since` and `cursor`; the page part of the id
+    // tells apart snapshots from before a reload, when the history starts over
+    const KEPT_SNAPSHOTS = 10;
+    if (!window.__MCP_SNAPSHOTS__) {
+        window.__MCP_SNAPSHOTS__ = { page: Math.random().toString(36).slice(2, 8), next: 0, trees: new Map() };
+    }
+    const history = window.__MCP_SNAPSHOTS__;
+
+    let snapshotId;
+    let stored;
+    let offset = 0;
+    let previous;
+    if (options.cursor !== null) {
+        // Continue a truncated snapshot from the tree it was cut from
+        const split = options.cursor.lastIndexOf(':');
+        snapshotId = options.cursor.slice(0, split);
+        offset = Number(options.cursor.slice(split + 1));
+        stored = history.trees.get(snapshotId);
+        if (split < 0 || !stored || !Number.isInteger(offset) || offset < 0) {
+            throw new Error(`Snapshot cursor ${options.cursor} is no longer available (the page reloaded or it is too old). Take a new snapshot.`);
+        }
+    } else {
+        let rootEl = document.body;
+        if (options.root !== null) {
+            const found = typeof options.root === 'number' ? resolveRef(options.root) : strict(options.root);
+            if (found.error) throw new Error(found.error);
+            if (!found.el) throw new Error('Snapshot root not found: ' + options.root);
+            rootEl = found.el;
+        }
+        const built = buildTree(rootEl);
+        const roots = Array.isArray(built) ? built : built ? [built] : [];
+        if (options.max_depth !== null) {
+            for (const root of roots) {
+                limitDepth(root, 1);
+            }
+        }
+        stored = { roots, nodes: flatten(preorder(roots)) };
+
+        snapshotId = `${history.page}-${++history.next}`;
+        previous = options.since !== null ? history.trees.get(options.since) : undefined;
+        history.trees.set(snapshotId, stored);
+        while (history.trees.size > KEPT_SNAPSHOTS) {
+            history.trees.delete(history.trees.keys().next().value);
+        }
+    }
+
+    let snapshot;
+    let tree;
+    let diff;
+    let note;
+    let truncated;
+    if (previous) {
+        diff = diffTrees(previous.nodes, stored.nodes);
+        // Added and changed nodes count against max_nodes like tree nodes do;
+        // a diff has no cursor, the full tree is a snapshot without since
+        const changes = diff.added.length + diff.changed.length;
+        if (changes > options.max_nodes) {
+            diff.added = diff.added.slice(0, options.max_nodes);
+            diff.changed = diff.changed.slice(0, options.max_nodes - diff.added.length);
+            truncated = { remaining: changes - options.max_nodes };
+        }
+        if (options.format === 'text') {
+            snapshot = diffToText(diff, previous.nodes, stored.nodes) || `No changes since snapshot ${options.since}\n`;
+            if (truncated) {
+                snapshot += `... ${truncated.remaining} more changes, call snapshot without since (or with a narrower root) to see the whole tree\n`;
+            }
+        }
+    } else {
+        if (options.since !== null) {
+            note = `Snapshot ${options.since} is no longer available (the page reloaded or it is too old), returning the full tree`;
+        }
+        const entries = preorder(stored.roots);
+        const page = entries.slice(offset, offset + options.max_nodes);
+        const remaining = entries.length - offset - page.length;
+        if (remaining > 0) {
+            truncated = { remaining, cursor: `${snapshotId}:${offset + page.length}` };
+        }
+        if (options.format === 'json') {
+            tree = entriesToTree(page);
+        } else {
+            snapshot = entriesToText(page);
+            if (truncated) {
+                snapshot += `... ${remaining} more nodes, call snapshot with cursor "${truncated.cursor}" to continue\n`;
+            }
+        }
+    }
+
+    // Build health status from HMR monitoring
+    const buildHealth = {
+        frontend: (window.__MCP_BUILD_LOGS__ || []).some(log => log.level === 'error') ? 'error' : 'healthy',
+        hmrConnected: window.__MCP_HMR_STATUS__ === 'connected',
+        lastError: (window.__MCP_BUILD_LOGS__ || []).filter(log => log.level === 'error').slice(-1)[0] || null,
+    };
+
+    // Include warning if there are build errors
+    const result = {
+        window: window.__MCP_WINDOW_LABEL__ || 'main',
+        webview: window.__MCP_WEBVIEW_LABEL__ || window.__MCP_WINDOW_LABEL__ || 'main',
+        url: window.location.href,
+        title: document.title,
+        snapshotId: snapshotId,
+        snapshot: snapshot,
+        tree: tree,
+        buildHealth: buildHealth,
+    };
+    if (truncated) {
+        result.truncated = truncated;
+    }
+    if (diff) {
+        result.since = options.since;
+        result.diff = diff;
+    }
+    if (note) {
+        result.note = note;
+    }
+
+    if (buildHealth.frontend === 'error' && buildHealth.lastError) {
+        result.warning = `Build error: ${buildHealth.lastError.message}`;
+        if (buildHealth.lastError.file) {
+            result.warning += ` (${buildHealth.lastError.file}:${buildHealth.lastError.line || '?'})`;
+        }
+    }
+
+    return result;
+"#;
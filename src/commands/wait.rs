@@ -0,0 +1,298 @@
+//! Wait commands
+
+/// Element states `wait_for` can wait for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElementState {
+    /// Present in the document
+    Attached,
+    /// Removed from the document (or never added)
+    Detached,
+    /// Attached, rendered and with a non-empty box
+    #[default]
+    Visible,
+    /// Detached or not visible
+    Hidden,
+    /// Attached and not disabled
+    Enabled,
+}
+
+impl ElementState {
+    fn name(self) -> &'static str {
+        match self {
+            ElementState::Attached => "attached",
+            ElementState::Detached => "detached",
+            ElementState::Visible => "visible",
+            ElementState::Hidden => "hidden",
+            ElementState::Enabled => "enabled",
+        }
+    }
+}
+
+/// A condition for `wait_for`
+///
+/// Exactly one of an element (`selector` or `ref`, with `state`), `text`,
+/// `url` or `predicate` must be given.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct WaitCondition {
+    pub selector: Option<String>,
+    #[serde(rename = "ref")]
+    pub ref_num: Option<u32>,
+    pub state: ElementState,
+    /// Text that must appear in the page's rendered text
+    pub text: Option<String>,
+    /// Substring of the URL, or a regular expression written as `/pattern/flags`
+    pub url: Option<String>,
+    /// JavaScript expression (or function) whose result must be truthy
+    pub predicate: Option<String>,
+    pub timeout_ms: u64,
+    /// Polling interval for changes a `MutationObserver` cannot see
+    pub poll_ms: u64,
+}
+
+impl Default for WaitCondition {
+    fn default() -> Self {
+        Self {
+            selector: None,
+            ref_num: None,
+            state: ElementState::default(),
+            text: None,
+            url: None,
+            predicate: None,
+            timeout_ms: 10_000,
+            poll_ms: 100,
+        }
+    }
+}
+
+impl WaitCondition {
+    /// Read a wait condition from request params
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        let condition: WaitCondition = serde_json::from_value(params.clone())
+            .map_err(|e| format!("Invalid wait condition: {}", e))?;
+        let element = condition.selector.is_some() || condition.ref_num.is_some();
+        let kinds = [
+            element,
+            condition.text.is_some(),
+            condition.url.is_some(),
+            condition.predicate.is_some(),
+        ];
+        if kinds.iter().filter(|&&kind| kind).count() != 1 {
+            return Err(
+                "Exactly one of selector/ref, text, url or predicate must be provided".to_string(),
+            );
+        }
+        if condition.selector.is_some() && condition.ref_num.is_some() {
+            return Err("Provide either selector or ref, not both".to_string());
+        }
+        if condition.poll_ms == 0 {
+            return Err("poll_ms must be positive".to_string());
+        }
+        Ok(condition)
+    }
+
+    /// Human-readable description for timeout errors
+    pub fn describe(&self) -> String {
+        if let Some(selector) = &self.selector {
+            format!("selector '{}' to be {}", selector, self.state.name())
+        } else if let Some(ref_num) = self.ref_num {
+            format!("ref={} to be {}", ref_num, self.state.name())
+        } else if let Some(text) = &self.text {
+            format!("text {:?}", text)
+        } else if let Some(url) = &self.url {
+            format!("URL matching '{}'", url)
+        } else {
+            format!("predicate `{}`", self.predicate.as_deref().unwrap_or(""))
+        }
+    }
+}
+
+/// JavaScript code to wait up to `timeout_ms` for a condition
+///
+/// Rechecks on every DOM mutation and every `poll_ms`. Resolves to
+/// `{ success, met, state }`, where `state` is the last observed state, or to
+/// `{ success: false, error }` if the condition cannot be evaluated.
+pub fn wait_for_js(condition: &WaitCondition, timeout_ms: u64) -> String {
+    format!(
+        r#"
+{require_locator}
+const condition = {condition};
+const timeoutMs = {timeout_ms};
+
+const isVisible = (el) => {{
+    const style = window.getComputedStyle(el);
+    if (style.display === 'none' || style.visibility === 'hidden') return false;
+    const r = el.getBoundingClientRect();
+    return r.width > 0 && r.height > 0;
+}};
+
+const urlMatches = (pattern, url) => {{
+    const regex = pattern.match(/^\/(.+)\/([a-z]*)$/);
+    return regex ? new RegExp(regex[1], regex[2]).test(url) : url.includes(pattern);
+}};
+
+let predicate = null;
+if (condition.predicate !== null) {{
+    predicate = new Function('return (' + condition.predicate + ');');
+}}
+
+// Returns whether the condition holds and what was observed
+const check = async () => {{
+    if (condition.text !== null) {{
+        const text = document.body ? document.body.innerText : '';
+        return {{ met: text.includes(condition.text), state: {{ textFound: text.includes(condition.text), title: document.title }} }};
+    }}
+    if (condition.url !== null) {{
+        const url = window.location.href;
+        return {{ met: urlMatches(condition.url, url), state: {{ url: url }} }};
+    }}
+    if (predicate) {{
+        let value = predicate();
+        if (typeof value === 'function') value = value();
+        value = await value;
+        return {{ met: !!value, state: {{ value: String(value).slice(0, 200) }} }};
+    }}
+
+    let el = null;
+    if (condition.ref !== null) {{
+        // A removed element is detached; a ref never assigned is an error
+        const found = window.__MCP_LOCATOR__.resolveRef(condition.ref);
+        if (found.error && !found.removed) throw new Error(found.error);
+        el = found.el || null;
+    }} else {{
+        // A locator matching several elements is an error, not "attached"
+        const found = window.__MCP_LOCATOR__.strict(condition.selector);
+        if (found.error) throw new Error(found.error);
+        el = found.el;
+    }}
+    const attached = !!el && el.isConnected;
+    const visible = attached && isVisible(el);
+    const enabled = attached && !el.matches(':disabled') && el.getAttribute('aria-disabled') !== 'true';
+    const met = {{
+        attached: attached,
+        detached: !attached,
+        visible: visible,
+        hidden: !visible,
+        enabled: enabled,
+    }}[condition.state];
+    return {{ met: met, state: {{ attached: attached, visible: visible, enabled: enabled }} }};
+}};
+
+let last;
+try {{
+    last = await check();
+}} catch (e) {{
+    return {{ success: false, error: 'Failed to evaluate condition: ' + (e.message || String(e)) }};
+}}
+if (last.met) {{
+    return {{ success: true, met: true, state: last.state }};
+}}
+
+return await new Promise((resolve) => {{
+    let done = false;
+    let checking = false;
+    let observer;
+    let timer;
+    let deadline;
+    const finish = (result) => {{
+        if (done) return;
+        done = true;
+        observer.disconnect();
+        clearInterval(timer);
+        clearTimeout(deadline);
+        resolve(result);
+    }};
+    const recheck = async () => {{
+        if (done || checking) return;
+        checking = true;
+        try {{
+            last = await check();
+            if (last.met) finish({{ success: true, met: true, state: last.state }});
+        }} catch (e) {{
+            finish({{ success: false, error: 'Failed to evaluate condition: ' + (e.message || String(e)) }});
+        }} finally {{
+            checking = false;
+        }}
+    }};
+
+    observer = new MutationObserver(recheck);
+    observer.observe(document, {{ subtree: true, childList: true, attributes: true, characterData: true }});
+    // Polling catches what mutations miss: styles, layout, URL changes and predicates
+    timer = setInterval(recheck, condition.poll_ms);
+    deadline = setTimeout(() => finish({{ success: true, met: false, state: last.state }}), timeoutMs);
+}});
+"#,
+        require_locator = super::REQUIRE_LOCATOR_JS,
+        condition = serde_json::to_string(condition).unwrap(),
+        timeout_ms = timeout_ms
+    )
+}
+
+/// Options for `wait_for_idle`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct IdleOptions {
+    /// How long there must be no in-flight requests or IPC calls
+    pub idle_ms: u64,
+    pub timeout_ms: u64,
+}
+
+impl Default for IdleOptions {
+    fn default() -> Self {
+        Self {
+            idle_ms: 500,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+impl IdleOptions {
+    /// Read idle options from request params
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        serde_json::from_value(params.clone()).map_err(|e| format!("Invalid idle options: {}", e))
+    }
+}
+
+/// JavaScript code to wait up to `timeout_ms` for network and IPC to go idle
+///
+/// Relies on the request tracking installed with the bridge. Idle means
+/// nothing in flight and no request started or finished for `idle_ms`.
+/// Resolves like [`wait_for_js`], with the still-pending requests in `state`.
+pub fn wait_for_idle_js(options: &IdleOptions, timeout_ms: u64) -> String {
+    format!(
+        r#"
+const idleMs = {idle_ms};
+const timeoutMs = {timeout_ms};
+
+if (!(window.__MCP_PENDING_REQUESTS__ instanceof Map)) {{
+    return {{ success: false, error: 'Request tracking is not available in this page. Reload the page or call initMcpBridge().' }};
+}}
+
+const check = () => {{
+    const now = Date.now();
+    const quietMs = now - (window.__MCP_LAST_REQUEST_ACTIVITY__ || 0);
+    const pending = Array.from(window.__MCP_PENDING_REQUESTS__.values()).slice(0, 20).map((request) => ({{
+        type: request.type,
+        method: request.method,
+        url: request.url,
+        elapsed_ms: now - request.timestamp,
+    }}));
+    return {{
+        met: window.__MCP_PENDING_REQUESTS__.size === 0 && quietMs >= idleMs,
+        state: {{ in_flight: window.__MCP_PENDING_REQUESTS__.size, idle_ms: quietMs, pending: pending }},
+    }};
+}};
+
+const deadline = Date.now() + timeoutMs;
+let last = check();
+while (!last.met && Date.now() < deadline) {{
+    await new Promise((resolve) => setTimeout(resolve, Math.min(50, Math.max(0, deadline - Date.now()))));
+    last = check();
+}}
+return {{ success: true, met: last.met, state: last.state }};
+"#,
+        idle_ms = options.idle_ms,
+        timeout_ms = timeout_ms
+    )
+}
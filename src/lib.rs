@@ -22,18 +22,25 @@
 pub mod commands;
 pub mod debug_server;
 pub mod protocol;
+pub mod recording;
+pub mod window_events;
 
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tauri::{
     plugin::{Builder, TauriPlugin},
-    AppHandle, Manager, Runtime, State, Webview,
+    AppHandle, Manager, RunEvent, Runtime, State, Webview, Window,
 };
-use tokio::sync::{oneshot, Mutex};
+use tokio::sync::{oneshot, Mutex, Notify};
 use tracing::{debug, error, info, warn};
 
+use commands::annotate::RefRect;
+use commands::compare::{self, CompareOptions, StabilityOptions};
+use commands::screenshot::{CaptureSetup, ImageOptions, ImageTransform, Masks, OutputFormat};
 use debug_server::DebugServer;
-use protocol::{JsonRpcRequest, JsonRpcResponse, EVAL_ERROR, METHOD_NOT_FOUND};
+use protocol::{JsonRpcRequest, JsonRpcResponse, EVAL_ERROR, INVALID_PARAMS, METHOD_NOT_FOUND};
+use recording::{Recording, RecordingFormat, RecordingOptions};
+use window_events::WindowEventLog;
 
 /// Eval result from JS bridge
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -47,10 +54,18 @@ pub struct EvalResult {
 
 /// Plugin state
 pub struct McpState {
-    /// Set of window labels where bridge has been initialized
-    initialized_windows: Mutex<HashSet<String>>,
+    /// Set of webview labels where bridge has been initialized
+    initialized_webviews: Mutex<HashSet<String>>,
     /// Pending eval results waiting for JS callback
     pending: Mutex<HashMap<String, oneshot::Sender<Result<serde_json::Value, String>>>>,
+    /// Recent window lifecycle events
+    window_events: WindowEventLog,
+    /// Notified whenever a new window is created
+    window_created: Notify,
+    /// Screen recording in progress, if any
+    recording: Mutex<Option<Recording>>,
+    /// Coordinate transform of the latest screenshot of each webview
+    screenshot_transforms: Mutex<HashMap<String, ImageTransform>>,
     /// Debug server
     debug_server: Arc<DebugServer>,
 }
@@ -58,18 +73,22 @@ pub struct McpState {
 impl McpState {
     fn new(debug_server: Arc<DebugServer>) -> Self {
         Self {
-            initialized_windows: Mutex::new(HashSet::new()),
+            initialized_webviews: Mutex::new(HashSet::new()),
             pending: Mutex::new(HashMap::new()),
+            window_events: WindowEventLog::default(),
+            window_created: Notify::new(),
+            recording: Mutex::new(None),
+            screenshot_transforms: Mutex::new(HashMap::new()),
             debug_server,
         }
     }
 
-    async fn is_window_initialized(&self, label: &str) -> bool {
-        self.initialized_windows.lock().await.contains(label)
+    async fn is_webview_initialized(&self, label: &str) -> bool {
+        self.initialized_webviews.lock().await.contains(label)
     }
 
-    async fn set_window_initialized(&self, label: String) {
-        self.initialized_windows.lock().await.insert(label);
+    async fn set_webview_initialized(&self, label: String) {
+        self.initialized_webviews.lock().await.insert(label);
     }
 }
 
@@ -79,6 +98,18 @@ pub trait CommandHandler: Send + Sync {
     async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse;
 }
 
+/// Maximum number of viewport-sized frames stitched into a full-page screenshot
+const MAX_FULL_PAGE_FRAMES: usize = 100;
+
+/// Maximum number of pointer moves in a drag
+const MAX_DRAG_STEPS: u64 = 200;
+
+/// Error returned when the page does not answer an eval in time
+const EVAL_TIMEOUT: &str = "Timeout waiting for eval result";
+
+/// Longest single in-page wait; `wait_for` re-arms it so waits survive navigations
+const WAIT_SLICE_MS: u64 = 5_000;
+
 /// JavaScript code to auto-inject minimal MCP bridge
 /// This enables multi-window support without requiring manual initMcpBridge() in each window
 const BRIDGE_INIT_JS: &str = r#"
@@ -86,20 +117,77 @@ const BRIDGE_INIT_JS: &str = r#"
     if (window.__MCP_BRIDGE__?.initialized) return true;
 
     window.__MCP_BRIDGE__ = { initialized: true };
-    window.__MCP_REF_MAP__ = window.__MCP_REF_MAP__ || new Map();
     window.__MCP_CONSOLE_LOGS__ = window.__MCP_CONSOLE_LOGS__ || [];
     window.__MCP_NETWORK_LOGS__ = window.__MCP_NETWORK_LOGS__ || [];
     window.__MCP_BUILD_LOGS__ = window.__MCP_BUILD_LOGS__ || [];
     window.__MCP_HMR_UPDATES__ = window.__MCP_HMR_UPDATES__ || [];
     window.__MCP_HMR_STATUS__ = window.__MCP_HMR_STATUS__ || 'unknown';
     window.__MCP_HMR_LAST_SUCCESS__ = window.__MCP_HMR_LAST_SUCCESS__ || null;
+    window.__MCP_PENDING_REQUESTS__ = window.__MCP_PENDING_REQUESTS__ || new Map();
+    window.__MCP_LAST_REQUEST_ACTIVITY__ = window.__MCP_LAST_REQUEST_ACTIVITY__ || Date.now();
+
+    // Track in-flight fetch, XHR and invoke calls for wait_for_idle.
+    // initMcpBridge() leaves tracking to these wrappers once they are installed.
+    if (!window.__MCP_REQUEST_TRACKING__) {
+        window.__MCP_REQUEST_TRACKING__ = true;
+        let nextRequestId = 0;
+        const track = (request) => {
+            const id = ++nextRequestId;
+            window.__MCP_PENDING_REQUESTS__.set(id, Object.assign({}, request, { timestamp: Date.now() }));
+            window.__MCP_LAST_REQUEST_ACTIVITY__ = Date.now();
+            return id;
+        };
+        const untrack = (id) => {
+            if (window.__MCP_PENDING_REQUESTS__.delete(id)) {
+                window.__MCP_LAST_REQUEST_ACTIVITY__ = Date.now();
+            }
+        };
+
+        // invoke goes through fetch to ipc://localhost/<command>; the bridge's
+        // own IPC (eval results) is skipped so the app can look idle
+        const originalFetch = window.fetch;
+        window.fetch = function(input, init) {
+            const url = typeof input === 'string' ? input : input instanceof URL ? input.href : input.url;
+            const ipc = url.match(/^(?:ipc:\/\/localhost|https?:\/\/ipc\.localhost)\/([^?#]+)/);
+            const command = ipc ? decodeURIComponent(ipc[1]) : null;
+            let id = null;
+            if (command === null) {
+                id = track({ type: 'fetch', method: (init && init.method) || 'GET', url: url });
+            } else if (!command.startsWith('plugin:mcp|')) {
+                id = track({ type: 'ipc', method: 'invoke', url: command });
+            }
+            const response = originalFetch.call(window, input, init);
+            if (id !== null) {
+                response.then(() => untrack(id), () => untrack(id));
+            }
+            return response;
+        };
 
-    // Get window label from Tauri internals
+        const originalOpen = XMLHttpRequest.prototype.open;
+        const originalSend = XMLHttpRequest.prototype.send;
+        XMLHttpRequest.prototype.open = function(method, url) {
+            this.__mcp_request = { type: 'xhr', method: method, url: String(url) };
+            return originalOpen.apply(this, arguments);
+        };
+        XMLHttpRequest.prototype.send = function() {
+            const id = track(this.__mcp_request || { type: 'xhr', method: 'GET', url: '' });
+            // loadend follows load, error, abort and timeout alike
+            this.addEventListener('loadend', () => untrack(id), { once: true });
+            return originalSend.apply(this, arguments);
+        };
+    }
+
+    // Get window and webview labels from Tauri internals
     try {
         window.__MCP_WINDOW_LABEL__ = window.__TAURI_INTERNALS__.metadata.currentWindow.label;
     } catch (e) {
         window.__MCP_WINDOW_LABEL__ = 'main';
     }
+    try {
+        window.__MCP_WEBVIEW_LABEL__ = window.__TAURI_INTERNALS__.metadata.currentWebview.label;
+    } catch (e) {
+        window.__MCP_WEBVIEW_LABEL__ = window.__MCP_WINDOW_LABEL__;
+    }
 
     window.__MCP_EVAL__ = async function(requestId, script) {
         let result;
@@ -114,7 +202,7 @@ const BRIDGE_INIT_JS: &str = r#"
         await window.__TAURI_INTERNALS__.invoke('plugin:mcp|eval_result', { result: result });
     };
 
-    console.log('[tauri-plugin-mcp] Bridge auto-injected for window:', window.__MCP_WINDOW_LABEL__);
+    console.log('[tauri-plugin-mcp] Bridge auto-injected for webview:', window.__MCP_WEBVIEW_LABEL__);
     return true;
 })();
 "#;
@@ -130,55 +218,933 @@ impl<R: Runtime> IpcCommandHandler<R> {
         Self { app, state }
     }
 
-    /// Get webview by label, or return focused/first window as fallback
-    fn get_webview(
-        &self,
-        window_label: Option<&str>,
-    ) -> Result<tauri::WebviewWindow<R>, String> {
-        let webviews = self.app.webview_windows();
+    /// Get window by label, or return focused/first window as fallback
+    fn get_window(&self, window_label: Option<&str>) -> Result<Window<R>, String> {
+        let windows = self.app.windows();
 
         if let Some(label) = window_label {
             // Explicit window label specified
-            webviews
+            windows
                 .get(label)
                 .cloned()
                 .ok_or_else(|| format!("Window '{}' not found", label))
         } else {
             // Try focused window first
-            for (_, window) in &webviews {
-                if window.is_focused().unwrap_or(false) {
-                    return Ok(window.clone());
-                }
+            if let Some(window) = windows.values().find(|window| {
+                window.is_focused().unwrap_or(false) && !window.webviews().is_empty()
+            }) {
+                return Ok(window.clone());
             }
-            // Fallback to first window
-            webviews
+            // Fallback to first window hosting a webview
+            windows
                 .values()
-                .next()
+                .find(|window| !window.webviews().is_empty())
                 .cloned()
                 .ok_or_else(|| "No webview available".to_string())
         }
     }
 
-    /// Execute JavaScript via IPC bridge on a specific window and wait for result
-    /// Automatically injects the bridge if not initialized for this window
+    /// Get webview by label, or the primary webview of the target window
+    ///
+    /// A webview window's own webview shares its label; for windows hosting
+    /// several child webviews the first one is used unless `webview` is given.
+    fn get_webview(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+    ) -> Result<Webview<R>, String> {
+        if let Some(label) = webview_label {
+            let webview = self
+                .app
+                .get_webview(label)
+                .ok_or_else(|| format!("Webview '{}' not found", label))?;
+            if let Some(window) = window_label {
+                if webview.window().label() != window {
+                    return Err(format!(
+                        "Webview '{}' does not belong to window '{}'",
+                        label, window
+                    ));
+                }
+            }
+            return Ok(webview);
+        }
+
+        let window = self.get_window(window_label)?;
+        let webviews = window.webviews();
+        webviews
+            .iter()
+            .find(|webview| webview.label() == window.label())
+            .cloned()
+            .or_else(|| webviews.into_iter().next())
+            .ok_or_else(|| format!("Window '{}' has no webview", window.label()))
+    }
+
+    /// Resolve the window to act on natively: the parent of `webview` if given,
+    /// otherwise the labelled or focused window
+    fn target_window(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+    ) -> Result<Window<R>, String> {
+        match webview_label {
+            Some(_) => self
+                .get_webview(window_label, webview_label)
+                .map(|webview| webview.window()),
+            None => self.get_window(window_label),
+        }
+    }
+
+    /// Describe a window so it can be matched against native (xcap) windows
+    fn window_matcher(window: &Window<R>) -> commands::screenshot::WindowMatcher {
+        let position = window.outer_position().unwrap_or_default();
+        let size = window.outer_size().unwrap_or_default();
+        #[cfg(windows)]
+        let native_id = window.hwnd().ok().map(|hwnd| hwnd.0 as usize as u32);
+        #[cfg(not(windows))]
+        let native_id = None;
+        commands::screenshot::WindowMatcher {
+            title: window.title().unwrap_or_default(),
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            scale_factor: window.scale_factor().unwrap_or(1.0),
+            native_id,
+        }
+    }
+
+    /// Physical-pixel layout of a webview inside its window
+    fn webview_geometry(webview: &Webview<R>) -> commands::screenshot::WebviewGeometry {
+        let window = webview.window();
+        let outer_position = window.outer_position().unwrap_or_default();
+        let inner_position = window.inner_position().unwrap_or_default();
+        let outer_size = window.outer_size().unwrap_or_default();
+        let inner_size = window.inner_size().unwrap_or_default();
+        let webview_position = webview.position().unwrap_or_default();
+        commands::screenshot::WebviewGeometry {
+            outer_width: outer_size.width,
+            outer_height: outer_size.height,
+            client_x: inner_position.x - outer_position.x,
+            client_y: inner_position.y - outer_position.y,
+            inner_width: inner_size.width,
+            inner_height: inner_size.height,
+            webview_x: webview_position.x,
+            webview_y: webview_position.y,
+        }
+    }
+
+    /// Run a native capture off the async runtime, giving up after 5 seconds
+    async fn run_native<T, F>(task: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, String> + Send + 'static,
+    {
+        let native_task = tokio::task::spawn_blocking(task);
+        match tokio::time::timeout(tokio::time::Duration::from_secs(5), native_task).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => Err(format!("Task panicked: {}", e)),
+            Err(_) => Err("Native capture timed out".to_string()),
+        }
+    }
+
+    /// Describe a webview with its parent window and bounds (physical pixels)
+    fn webview_info(webview: &Webview<R>) -> serde_json::Value {
+        let position = webview.position().ok();
+        let size = webview.size().ok();
+        serde_json::json!({
+            "label": webview.label(),
+            "window": webview.window().label(),
+            "url": webview.url().map(|u| u.to_string()).ok(),
+            "bounds": match (position, size) {
+                (Some(p), Some(s)) => serde_json::json!({
+                    "x": p.x,
+                    "y": p.y,
+                    "width": s.width,
+                    "height": s.height
+                }),
+                _ => serde_json::Value::Null,
+            }
+        })
+    }
+
+    /// Describe the current state of a window (physical pixels)
+    fn window_state(window: &Window<R>) -> serde_json::Value {
+        let position = window.outer_position().ok();
+        let size = window.inner_size().ok();
+        serde_json::json!({
+            "label": window.label(),
+            "title": window.title().unwrap_or_default(),
+            "focused": window.is_focused().unwrap_or(false),
+            "visible": window.is_visible().unwrap_or(false),
+            "minimized": window.is_minimized().unwrap_or(false),
+            "maximized": window.is_maximized().unwrap_or(false),
+            "fullscreen": window.is_fullscreen().unwrap_or(false),
+            "scale_factor": window.scale_factor().unwrap_or(1.0),
+            "position": position.map(|p| serde_json::json!({
+                "x": p.x,
+                "y": p.y
+            })),
+            "size": size.map(|s| serde_json::json!({
+                "width": s.width,
+                "height": s.height
+            }))
+        })
+    }
+
+    /// Find a window matching the given label, title substring and URL substring
+    ///
+    /// Only windows created after the `created_since` event cursor are considered.
+    fn find_window(
+        &self,
+        label: Option<&str>,
+        title: Option<&str>,
+        url: Option<&str>,
+        created_since: u64,
+    ) -> Option<Window<R>> {
+        let (events, _, _) = self.state.window_events.since(created_since, None);
+        let created: HashSet<String> = events
+            .into_iter()
+            .filter(|e| e.event == "created")
+            .map(|e| e.window)
+            .collect();
+
+        self.app.windows().into_values().find(|window| {
+            if !created.contains(window.label()) {
+                return false;
+            }
+            if label.is_some_and(|l| window.label() != l) {
+                return false;
+            }
+            if let Some(t) = title {
+                if !window.title().unwrap_or_default().contains(t) {
+                    return false;
+                }
+            }
+            if let Some(u) = url {
+                let matches = window.webviews().iter().any(|webview| {
+                    webview
+                        .url()
+                        .map(|current| current.as_str().contains(u))
+                        .unwrap_or(false)
+                });
+                if !matches {
+                    return false;
+                }
+            }
+            true
+        })
+    }
+
+    /// Apply an operation to the target window and return its resulting state
+    async fn window_action<F>(
+        &self,
+        id: Option<serde_json::Value>,
+        window_label: Option<&str>,
+        action: F,
+    ) -> JsonRpcResponse
+    where
+        F: FnOnce(&Window<R>) -> tauri::Result<()>,
+    {
+        let window = match self.get_window(window_label) {
+            Ok(window) => window,
+            Err(e) => return JsonRpcResponse::error(id, EVAL_ERROR, e),
+        };
+        if let Err(e) = action(&window) {
+            return JsonRpcResponse::error(id, EVAL_ERROR, e.to_string());
+        }
+        // Window managers apply most changes asynchronously; give them a moment
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        JsonRpcResponse::success(id, Self::window_state(&window))
+    }
+
+    /// Capture the whole scrollable page by scrolling its container and stitching
+    /// native captures, falling back to rendering the document in the page
+    async fn full_page_screenshot(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        scroll_container: Option<&str>,
+        image_options: &ImageOptions,
+        setup: &CaptureSetup,
+    ) -> Result<serde_json::Value, String> {
+        let webview = self.get_webview(window_label, webview_label)?;
+
+        // Pick the scroll container and read its visible rect and scroll metrics
+        let js = commands::scroll_container_js(scroll_container);
+        let container = self
+            .eval_with_result_on_window(window_label, webview_label, &js)
+            .await?;
+        if !container["success"].as_bool().unwrap_or(false) {
+            let message = container["error"]
+                .as_str()
+                .unwrap_or("Scroll container not found");
+            return Err(message.to_string());
+        }
+        let rect: commands::screenshot::Rect = serde_json::from_value(container["rect"].clone())
+            .map_err(|e| format!("Invalid scroll container rect: {}", e))?;
+        let original_top = container["scrollTop"].as_f64().unwrap_or(0.0);
+        let client_height = container["clientHeight"]
+            .as_f64()
+            .unwrap_or(rect.height)
+            .max(1.0);
+        let full_height = container["scrollHeight"]
+            .as_f64()
+            .unwrap_or(client_height)
+            .max(client_height);
+        let device_pixel_ratio = container["devicePixelRatio"].as_f64().unwrap_or(1.0);
+
+        // Very tall pages are truncated rather than captured frame by frame forever
+        let scroll_height = full_height.min(client_height * MAX_FULL_PAGE_FRAMES as f64);
+        let max_top = scroll_height - client_height;
+        let mut positions = Vec::new();
+        let mut top = 0.0;
+        while top < max_top {
+            positions.push(top);
+            top += client_height;
+        }
+        positions.push(max_top);
+
+        let matcher = Self::window_matcher(&webview.window());
+        let geometry = Self::webview_geometry(&webview);
+        let pid = std::process::id();
+        let captured: Result<_, String> = async {
+            let mut frames = Vec::with_capacity(positions.len());
+            let mut scale = 1.0;
+            for position in positions {
+                let js = commands::scroll_container_to_js(position);
+                let scrolled = self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await?;
+                let offset = scrolled["scrollTop"].as_f64().unwrap_or(position);
+                // Masked elements move with the content, so resolve them per frame
+                let masks = self
+                    .capture_masks(window_label, webview_label, setup)
+                    .await?;
+
+                let matcher = matcher.clone();
+                let frame_options = image_options.clone();
+                let (frame, frame_scale) = Self::run_native(move || {
+                    let (mut frame, frame_scale) = commands::screenshot::capture_region_by_pid(
+                        pid,
+                        &matcher,
+                        &geometry,
+                        &rect,
+                        device_pixel_ratio,
+                    )?;
+                    masks.paint_region(&mut frame, &rect, frame_scale);
+                    // Shrink each frame as it arrives so the page is never held
+                    // at full resolution
+                    let factor = commands::screenshot::full_page_frame_factor(
+                        frame.width(),
+                        scroll_height * frame_scale,
+                        &frame_options,
+                    );
+                    let frame = commands::screenshot::resize_frame(frame, factor);
+                    Ok((frame, frame_scale * factor))
+                })
+                .await?;
+                scale = frame_scale;
+                frames.push((offset, frame));
+            }
+            Ok((frames, scale))
+        }
+        .await;
+
+        // Always restore the user's scroll position
+        let js = commands::scroll_container_to_js(original_top);
+        if let Err(e) = self
+            .eval_with_result_on_window(window_label, webview_label, &js)
+            .await
+        {
+            warn!("Failed to restore scroll position: {}", e);
+        }
+
+        let native_options = image_options.clone();
+        let native_result = match captured {
+            Ok((frames, scale)) => tokio::task::spawn_blocking(move || {
+                // The top of the scrolled content sits above the container by the
+                // restored scroll offset
+                let transform = ImageTransform::region(
+                    rect.x,
+                    rect.y - original_top,
+                    scale,
+                    device_pixel_ratio,
+                );
+                commands::screenshot::stitch_full_page(
+                    frames,
+                    scroll_height,
+                    &native_options,
+                    transform,
+                )
+            })
+            .await
+            .unwrap_or_else(|e| Err(format!("Task panicked: {}", e))),
+            Err(e) => Err(e),
+        };
+
+        match native_result {
+            Ok(mut result) => {
+                if scroll_height < full_height {
+                    result["truncated"] = serde_json::json!(true);
+                }
+                Ok(result)
+            }
+            Err(e) => {
+                warn!(
+                    "Native full-page screenshot failed: {}, falling back to JS",
+                    e
+                );
+                let masks = self
+                    .capture_masks(window_label, webview_label, setup)
+                    .await?;
+                let js = commands::screenshot_js(&serde_json::json!({
+                    "fullPage": true,
+                    "image": image_options,
+                    "masks": masks.rects,
+                    "maskColor": setup.mask_color,
+                }));
+                self.eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+            }
+        }
+    }
+
+    /// Take a window (or full-page) screenshot as requested by `params`
+    /// Tries native capture first, falling back to in-page rendering
+    async fn capture_screenshot(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        params: &serde_json::Value,
+        image_options: &ImageOptions,
+        setup: &CaptureSetup,
+    ) -> Result<serde_json::Value, String> {
+        self.prepare_capture(window_label, webview_label, setup)
+            .await;
+
+        let full_page = params
+            .get("full_page")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let result = if full_page {
+            let scroll_container = params.get("scroll_container").and_then(|v| v.as_str());
+            self.full_page_screenshot(
+                window_label,
+                webview_label,
+                scroll_container,
+                image_options,
+                setup,
+            )
+            .await
+        } else {
+            let annotate = params
+                .get("annotate")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            self.window_screenshot(window_label, webview_label, image_options, setup, annotate)
+                .await
+        };
+
+        self.restore_capture(window_label, webview_label, setup)
+            .await;
+        result
+    }
+
+    /// Capture the visible content of the target window
+    /// With `annotate`, snapshot refs are drawn as numbered boxes (see [`commands::annotate`])
+    async fn window_screenshot(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        image_options: &ImageOptions,
+        setup: &CaptureSetup,
+        annotate: bool,
+    ) -> Result<serde_json::Value, String> {
+        let masks = self
+            .capture_masks(window_label, webview_label, setup)
+            .await?;
+        // Read ref positions up front so they match the captured frame
+        let refs = if annotate {
+            Some(self.ref_rects(window_label, webview_label).await?)
+        } else {
+            None
+        };
+        let matcher = Self::window_matcher(&self.target_window(window_label, webview_label)?);
+        let pid = std::process::id();
+        // Annotated captures are drawn on before they are written to `path`
+        let capture_options = ImageOptions {
+            path: image_options.path.clone().filter(|_| !annotate),
+            ..image_options.clone()
+        };
+        let native_options = capture_options.clone();
+        let native_masks = masks.clone();
+        let native_result = Self::run_native(move || {
+            commands::screenshot::capture_window_by_pid(
+                pid,
+                Some(&matcher),
+                &native_options,
+                &native_masks,
+            )
+        })
+        .await;
+
+        let mut result = match native_result {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Native screenshot failed: {}, falling back to JS", e);
+                let js = commands::screenshot_js(&serde_json::json!({
+                    "image": capture_options,
+                    "masks": masks.rects,
+                    "maskColor": setup.mask_color,
+                }));
+                self.eval_with_result_on_window(window_label, webview_label, &js)
+                    .await?
+            }
+        };
+
+        if let Some(refs) = refs {
+            let options = image_options.clone();
+            result = tokio::task::spawn_blocking(move || {
+                commands::annotate::annotate_screenshot(&mut result, &refs, &options)
+                    .map(|()| result)
+            })
+            .await
+            .unwrap_or_else(|e| Err(format!("Task panicked: {}", e)))?;
+        }
+        Ok(result)
+    }
+
+    /// Capture a single element by cropping the native window capture
+    async fn element_screenshot(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        ref_num: Option<u32>,
+        selector: Option<&str>,
+        image_options: &ImageOptions,
+        setup: &CaptureSetup,
+    ) -> Result<serde_json::Value, String> {
+        let webview = self.get_webview(window_label, webview_label)?;
+
+        // Get the element's bounding box (CSS pixels) and device pixel ratio
+        let js = commands::element_rect_js(ref_num, selector);
+        let element = self
+            .eval_with_result_on_window(window_label, webview_label, &js)
+            .await?;
+        if !element["success"].as_bool().unwrap_or(false) {
+            let message = element["error"].as_str().unwrap_or("Element not found");
+            return Err(message.to_string());
+        }
+        let rect: commands::screenshot::Rect = serde_json::from_value(element["rect"].clone())
+            .map_err(|e| format!("Invalid element rect: {}", e))?;
+        let device_pixel_ratio = element["devicePixelRatio"].as_f64().unwrap_or(1.0);
+        let masks = self
+            .capture_masks(window_label, webview_label, setup)
+            .await?;
+
+        let matcher = Self::window_matcher(&webview.window());
+        let geometry = Self::webview_geometry(&webview);
+        let pid = std::process::id();
+        let native_options = image_options.clone();
+        let native_masks = masks.clone();
+        let native_result = Self::run_native(move || {
+            commands::screenshot::capture_element_by_pid(
+                pid,
+                &matcher,
+                &geometry,
+                &rect,
+                device_pixel_ratio,
+                &native_options,
+                &native_masks,
+            )
+        })
+        .await;
+
+        match native_result {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                warn!(
+                    "Native element screenshot failed: {}, falling back to JS",
+                    e
+                );
+                let js = commands::screenshot_js(&serde_json::json!({
+                    "clip": rect,
+                    "image": image_options,
+                    "masks": masks.rects,
+                    "maskColor": setup.mask_color,
+                }));
+                self.eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+            }
+        }
+    }
+
+    /// Prepare the page for a deterministic capture (fonts, caret, animations)
+    ///
+    /// Skipped unless `setup` needs the page. Failures are only logged: the
+    /// capture itself is native and does not depend on the page answering.
+    async fn prepare_capture(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        setup: &CaptureSetup,
+    ) {
+        if !setup.needs_page() {
+            return;
+        }
+        let js = commands::prepare_capture_js(setup.disable_animations);
+        if let Err(e) = self
+            .eval_with_result_on_window(window_label, webview_label, &js)
+            .await
+        {
+            warn!("Failed to prepare page for capture: {}", e);
+        }
+    }
+
+    /// Undo [`Self::prepare_capture`]; failures are only logged
+    async fn restore_capture(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        setup: &CaptureSetup,
+    ) {
+        if !setup.needs_page() {
+            return;
+        }
+        if let Err(e) = self
+            .eval_with_result_on_window(window_label, webview_label, commands::RESTORE_CAPTURE_JS)
+            .await
+        {
+            warn!("Failed to restore page after capture: {}", e);
+        }
+    }
+
+    /// Resolve the elements to mask to their current viewport rects
+    /// The device pixel ratio native captures need for `transform` is the window's scale factor
+    async fn capture_masks(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        setup: &CaptureSetup,
+    ) -> Result<Masks, String> {
+        let webview = self.get_webview(window_label, webview_label)?;
+        let mut masks = Masks {
+            rects: Vec::new(),
+            color: setup.color()?,
+            geometry: Self::webview_geometry(&webview),
+            device_pixel_ratio: webview.window().scale_factor().unwrap_or(1.0),
+        };
+        // Nothing to resolve, so don't touch the page
+        if setup.mask.is_empty() {
+            return Ok(masks);
+        }
+        let js = commands::mask_rects_js(&setup.mask);
+        let result = self
+            .eval_with_result_on_window(window_label, webview_label, &js)
+            .await?;
+        if !result["success"].as_bool().unwrap_or(false) {
+            let message = result["error"].as_str().unwrap_or("Mask element not found");
+            return Err(message.to_string());
+        }
+        masks.rects = serde_json::from_value(result["rects"].clone())
+            .map_err(|e| format!("Invalid mask rects: {}", e))?;
+        Ok(masks)
+    }
+
+    /// Resolve the refs of the latest snapshot to their visible viewport rects
+    async fn ref_rects(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+    ) -> Result<Vec<RefRect>, String> {
+        let result = self
+            .eval_with_result_on_window(window_label, webview_label, commands::REF_RECTS_JS)
+            .await?;
+        if !result["success"].as_bool().unwrap_or(false) {
+            let message = result["error"]
+                .as_str()
+                .unwrap_or("Failed to read ref rects");
+            return Err(message.to_string());
+        }
+        serde_json::from_value(result["refs"].clone())
+            .map_err(|e| format!("Invalid ref rects: {}", e))
+    }
+
+    /// Run an in-page wait in slices, re-arming it after navigations
+    ///
+    /// `wait_js` builds the wait script for a slice length in milliseconds;
+    /// the script resolves to `{ success, met, state }`.
+    async fn wait_in_page(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        timeout_ms: u64,
+        description: &str,
+        wait_js: impl Fn(u64) -> String,
+    ) -> Result<serde_json::Value, String> {
+        let start = tokio::time::Instant::now();
+        let deadline = start + tokio::time::Duration::from_millis(timeout_ms);
+        let mut last_state = serde_json::Value::Null;
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let slice = remaining.min(tokio::time::Duration::from_millis(WAIT_SLICE_MS));
+            let js = wait_js(slice.as_millis() as u64);
+            let grace = tokio::time::Duration::from_secs(2);
+            match self
+                .eval_with_timeout(window_label, webview_label, &js, slice + grace)
+                .await
+            {
+                Ok(result) => {
+                    if !result["success"].as_bool().unwrap_or(false) {
+                        let message = result["error"].as_str().unwrap_or("Wait failed");
+                        return Err(message.to_string());
+                    }
+                    if result["met"].as_bool().unwrap_or(false) {
+                        return Ok(serde_json::json!({
+                            "met": true,
+                            "elapsed_ms": start.elapsed().as_millis() as u64,
+                            "state": result["state"],
+                        }));
+                    }
+                    last_state = result["state"].clone();
+                }
+                // A navigation discards the in-page wait; try again on the new page
+                Err(e) if e == EVAL_TIMEOUT => {
+                    last_state = serde_json::json!("page did not respond (navigating?)");
+                }
+                Err(e) => return Err(e),
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(format!(
+                    "Timeout after {}ms waiting for {} (last observed: {})",
+                    timeout_ms, description, last_state
+                ));
+            }
+        }
+    }
+
+    /// Capture the window, or an element's region, until consecutive frames stop changing
+    async fn wait_for_visual_stable(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        ref_num: Option<u32>,
+        selector: Option<&str>,
+        options: &StabilityOptions,
+    ) -> Result<serde_json::Value, String> {
+        let webview = self.get_webview(window_label, webview_label)?;
+
+        // The element is scrolled into view once and its region captured from then on
+        let region = if ref_num.is_some() || selector.is_some() {
+            let js = commands::element_rect_js(ref_num, selector);
+            let element = self
+                .eval_with_result_on_window(window_label, webview_label, &js)
+                .await?;
+            if !element["success"].as_bool().unwrap_or(false) {
+                let message = element["error"].as_str().unwrap_or("Element not found");
+                return Err(message.to_string());
+            }
+            let rect: commands::screenshot::Rect = serde_json::from_value(element["rect"].clone())
+                .map_err(|e| format!("Invalid element rect: {}", e))?;
+            let device_pixel_ratio = element["devicePixelRatio"].as_f64().unwrap_or(1.0);
+            Some((rect, device_pixel_ratio))
+        } else {
+            None
+        };
+
+        let matcher = Self::window_matcher(&webview.window());
+        let geometry = Self::webview_geometry(&webview);
+        let pid = std::process::id();
+        let interval = tokio::time::Duration::from_millis(options.interval_ms);
+        let start = tokio::time::Instant::now();
+        let deadline = start + tokio::time::Duration::from_millis(options.timeout_ms);
+
+        let mut previous = None;
+        let mut frames = 0;
+        let mut stable_frames = 0;
+        let mut last_diff = None;
+        loop {
+            let matcher = matcher.clone();
+            let threshold = options.threshold;
+            let previous_frame = previous.take();
+            let (frame, diff) = Self::run_native(move || {
+                let frame = match region {
+                    Some((rect, device_pixel_ratio)) => {
+                        commands::screenshot::capture_region_by_pid(
+                            pid,
+                            &matcher,
+                            &geometry,
+                            &rect,
+                            device_pixel_ratio,
+                        )?
+                        .0
+                    }
+                    None => commands::screenshot::capture_image_by_pid(pid, Some(&matcher))?,
+                };
+                let diff = previous_frame
+                    .map(|previous| compare::diff_percentage(&previous, &frame, threshold));
+                Ok((frame, diff))
+            })
+            .await?;
+            previous = Some(frame);
+            frames += 1;
+
+            if let Some(diff) = diff {
+                last_diff = Some(diff);
+                if diff <= options.max_diff_percentage {
+                    stable_frames += 1;
+                } else {
+                    stable_frames = 0;
+                }
+                if stable_frames >= options.stable_frames {
+                    return Ok(serde_json::json!({
+                        "stable": true,
+                        "elapsed_ms": start.elapsed().as_millis() as u64,
+                        "frames": frames,
+                        "diff_percentage": diff,
+                    }));
+                }
+            }
+
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Err(format!(
+                    "Timeout after {}ms waiting for visual stability ({} of {} frames unchanged, last difference {:.3}%)",
+                    options.timeout_ms,
+                    stable_frames,
+                    options.stable_frames,
+                    last_diff.unwrap_or(100.0)
+                ));
+            }
+            tokio::time::sleep(interval.min(deadline - now)).await;
+        }
+    }
+
+    /// Build the response for a screenshot, writing it to disk if a path was requested
+    /// Remembers the screenshot's transform for `click_at`/`element_at`
+    async fn screenshot_response(
+        &self,
+        id: Option<serde_json::Value>,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        result: Result<serde_json::Value, String>,
+        options: &ImageOptions,
+    ) -> JsonRpcResponse {
+        let mut result = match result {
+            Ok(result) => result,
+            Err(e) => return JsonRpcResponse::error(id, EVAL_ERROR, e),
+        };
+        if let Ok(transform) = serde_json::from_value(result["transform"].clone()) {
+            self.remember_transform(window_label, webview_label, transform)
+                .await;
+        }
+        // Native captures are already written; in-page renders still carry data
+        if let Some(path) = &options.path {
+            if result.get("data").is_some() {
+                if let Err(e) = commands::screenshot::save_image(&mut result, path) {
+                    return JsonRpcResponse::error(id, EVAL_ERROR, e);
+                }
+            }
+        }
+        JsonRpcResponse::success(id, result)
+    }
+
+    /// Store the transform of the latest screenshot of the target webview
+    async fn remember_transform(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        transform: ImageTransform,
+    ) {
+        if let Ok(webview) = self.get_webview(window_label, webview_label) {
+            self.state
+                .screenshot_transforms
+                .lock()
+                .await
+                .insert(webview.label().to_string(), transform);
+        }
+    }
+
+    /// Map the image point `x`/`y` in `params` to a viewport point in CSS pixels
+    ///
+    /// Uses `params.transform` if given, otherwise the transform of the latest
+    /// screenshot of the target webview.
+    async fn image_point(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
+        params: &serde_json::Value,
+    ) -> Result<(f64, f64), String> {
+        let (Some(x), Some(y)) = (
+            params.get("x").and_then(|v| v.as_f64()),
+            params.get("y").and_then(|v| v.as_f64()),
+        ) else {
+            return Err("x and y are required".to_string());
+        };
+        let transform = match params.get("transform") {
+            Some(transform) => serde_json::from_value(transform.clone())
+                .map_err(|e| format!("Invalid transform: {}", e))?,
+            None => {
+                let webview = self.get_webview(window_label, webview_label)?;
+                self.state
+                    .screenshot_transforms
+                    .lock()
+                    .await
+                    .get(webview.label())
+                    .copied()
+                    .ok_or_else(|| {
+                        format!(
+                            "No screenshot of webview '{}' yet. Take a screenshot first or pass transform.",
+                            webview.label()
+                        )
+                    })?
+            }
+        };
+        Ok(transform.image_to_css(x, y))
+    }
+
+    /// Execute JavaScript via IPC bridge on a specific webview and wait for result
+    /// Automatically injects the bridge if not initialized for this webview
     async fn eval_with_result_on_window(
         &self,
         window_label: Option<&str>,
+        webview_label: Option<&str>,
+        script: &str,
+    ) -> Result<serde_json::Value, String> {
+        self.eval_with_timeout(
+            window_label,
+            webview_label,
+            script,
+            tokio::time::Duration::from_secs(30),
+        )
+        .await
+    }
+
+    /// [`Self::eval_with_result_on_window`] for scripts that may run longer
+    async fn eval_with_timeout(
+        &self,
+        window_label: Option<&str>,
+        webview_label: Option<&str>,
         script: &str,
+        timeout: tokio::time::Duration,
     ) -> Result<serde_json::Value, String> {
-        // Get target window
-        let window = self.get_webview(window_label)?;
-        let label = window.label().to_string();
-
-        // Auto-inject bridge if not initialized for this window
-        if !self.state.is_window_initialized(&label).await {
-            info!("Auto-injecting MCP bridge for window: {}", label);
-            if let Err(e) = window.eval(BRIDGE_INIT_JS) {
+        // Get target webview
+        let webview = self.get_webview(window_label, webview_label)?;
+        let label = webview.label().to_string();
+
+        // Auto-inject bridge if not initialized for this webview
+        if !self.state.is_webview_initialized(&label).await {
+            info!("Auto-injecting MCP bridge for webview: {}", label);
+            if let Err(e) = webview.eval(BRIDGE_INIT_JS) {
                 return Err(format!("Failed to inject MCP bridge: {}", e));
             }
+            if let Err(e) = webview.eval(commands::PAGE_SCRIPTS_JS) {
+                return Err(format!("Failed to inject MCP page scripts: {}", e));
+            }
             // Wait a bit for the bridge to initialize
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-            self.state.set_window_initialized(label.clone()).await;
+            self.state.set_webview_initialized(label.clone()).await;
         }
 
         // Generate unique request ID
@@ -203,85 +1169,339 @@ impl<R: Runtime> IpcCommandHandler<R> {
             request_id, escaped_script
         );
 
-        if let Err(e) = window.eval(&js) {
+        if let Err(e) = webview.eval(&js) {
             let mut pending = self.state.pending.lock().await;
             pending.remove(&request_id);
             return Err(format!("Failed to execute script: {}", e));
         }
 
         // Wait for result with timeout
-        let timeout = tokio::time::Duration::from_secs(30);
         match tokio::time::timeout(timeout, rx).await {
             Ok(Ok(result)) => result,
             Ok(Err(_)) => Err("Channel closed unexpectedly".to_string()),
             Err(_) => {
                 let mut pending = self.state.pending.lock().await;
                 pending.remove(&request_id);
-                Err("Timeout waiting for eval result".to_string())
+                Err(EVAL_TIMEOUT.to_string())
             }
         }
     }
-
 }
 
 #[async_trait::async_trait]
 impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
     async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse {
         let id = request.id.clone();
-        // Extract optional window parameter for multi-window support
+        // Extract optional window/webview parameters for multi-window support
         let window_label = request.params.get("window").and_then(|v| v.as_str());
+        let webview_label = request.params.get("webview").and_then(|v| v.as_str());
 
         match request.method.as_str() {
             "ping" => JsonRpcResponse::success(id, serde_json::json!({"pong": true})),
 
             "list_windows" => {
-                let webviews = self.app.webview_windows();
-                let windows: Vec<serde_json::Value> = webviews
-                    .iter()
-                    .map(|(label, window)| {
-                        let size = window.inner_size().ok();
-                        serde_json::json!({
-                            "label": label,
-                            "title": window.title().unwrap_or_default(),
-                            "focused": window.is_focused().unwrap_or(false),
-                            "visible": window.is_visible().unwrap_or(false),
-                            "size": size.map(|s| serde_json::json!({
-                                "width": s.width,
-                                "height": s.height
-                            }))
-                        })
+                let windows: Vec<serde_json::Value> = self
+                    .app
+                    .windows()
+                    .values()
+                    .map(|window| {
+                        let mut state = Self::window_state(window);
+                        let webviews: Vec<serde_json::Value> =
+                            window.webviews().iter().map(Self::webview_info).collect();
+                        state["webviews"] = serde_json::json!(webviews);
+                        state
                     })
                     .collect();
                 JsonRpcResponse::success(id, serde_json::json!({ "windows": windows }))
             }
 
-            "focus_window" => {
-                let label = match window_label {
-                    Some(l) => l,
-                    None => {
-                        return JsonRpcResponse::error(
-                            id,
-                            EVAL_ERROR,
-                            "Window label required".to_string(),
-                        )
-                    }
-                };
-                let webviews = self.app.webview_windows();
-                if let Some(window) = webviews.get(label) {
-                    match window.set_focus() {
-                        Ok(_) => {
-                            JsonRpcResponse::success(id, serde_json::json!({ "focused": label }))
-                        }
-                        Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e.to_string()),
-                    }
-                } else {
-                    JsonRpcResponse::error(id, EVAL_ERROR, format!("Window '{}' not found", label))
+            "list_webviews" => {
+                let webviews: Vec<serde_json::Value> = self
+                    .app
+                    .webviews()
+                    .values()
+                    .filter(|webview| {
+                        window_label.is_none_or(|label| webview.window().label() == label)
+                    })
+                    .map(Self::webview_info)
+                    .collect();
+                JsonRpcResponse::success(id, serde_json::json!({ "webviews": webviews }))
+            }
+
+            "focus_window" => {
+                let label = match window_label {
+                    Some(l) => l,
+                    None => {
+                        return JsonRpcResponse::error(
+                            id,
+                            EVAL_ERROR,
+                            "Window label required".to_string(),
+                        )
+                    }
+                };
+                if let Some(window) = self.app.windows().get(label) {
+                    match window.set_focus() {
+                        Ok(_) => {
+                            JsonRpcResponse::success(id, serde_json::json!({ "focused": label }))
+                        }
+                        Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e.to_string()),
+                    }
+                } else {
+                    JsonRpcResponse::error(id, EVAL_ERROR, format!("Window '{}' not found", label))
+                }
+            }
+
+            "create_window" => {
+                let label = match request.params.get("label").and_then(|v| v.as_str()) {
+                    Some(l) => l,
+                    None => {
+                        return JsonRpcResponse::error(
+                            id,
+                            INVALID_PARAMS,
+                            "Window label required".to_string(),
+                        )
+                    }
+                };
+                if self.app.get_window(label).is_some() {
+                    return JsonRpcResponse::error(
+                        id,
+                        EVAL_ERROR,
+                        format!("Window '{}' already exists", label),
+                    );
+                }
+                let url = match request.params.get("url").and_then(|v| v.as_str()) {
+                    Some(url) => match url.parse::<tauri::Url>() {
+                        Ok(external) => tauri::WebviewUrl::External(external),
+                        Err(_) => tauri::WebviewUrl::App(url.into()),
+                    },
+                    None => tauri::WebviewUrl::default(),
+                };
+                let mut builder = tauri::WebviewWindowBuilder::new(&self.app, label, url);
+                if let Some(title) = request.params.get("title").and_then(|v| v.as_str()) {
+                    builder = builder.title(title);
+                }
+                let width = request.params.get("width").and_then(|v| v.as_f64());
+                let height = request.params.get("height").and_then(|v| v.as_f64());
+                if let (Some(width), Some(height)) = (width, height) {
+                    builder = builder.inner_size(width, height);
+                }
+                match builder.build() {
+                    Ok(webview_window) => {
+                        let window = webview_window.as_ref().window();
+                        JsonRpcResponse::success(id, Self::window_state(&window))
+                    }
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e.to_string()),
+                }
+            }
+
+            "close_window" => {
+                let label = match window_label {
+                    Some(l) => l,
+                    None => {
+                        return JsonRpcResponse::error(
+                            id,
+                            INVALID_PARAMS,
+                            "Window label required".to_string(),
+                        )
+                    }
+                };
+                match self.get_window(Some(label)) {
+                    Ok(window) => match window.close() {
+                        Ok(_) => {
+                            JsonRpcResponse::success(id, serde_json::json!({ "closed": label }))
+                        }
+                        Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e.to_string()),
+                    },
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "set_window_size" => {
+                let width = request.params.get("width").and_then(|v| v.as_f64());
+                let height = request.params.get("height").and_then(|v| v.as_f64());
+                let (width, height) = match (width, height) {
+                    (Some(w), Some(h)) => (w, h),
+                    _ => {
+                        return JsonRpcResponse::error(
+                            id,
+                            INVALID_PARAMS,
+                            "width and height required".to_string(),
+                        )
+                    }
+                };
+                self.window_action(id, window_label, |window| {
+                    window.set_size(tauri::LogicalSize::new(width, height))
+                })
+                .await
+            }
+
+            "set_window_position" => {
+                let x = request.params.get("x").and_then(|v| v.as_f64());
+                let y = request.params.get("y").and_then(|v| v.as_f64());
+                let (x, y) = match (x, y) {
+                    (Some(x), Some(y)) => (x, y),
+                    _ => {
+                        return JsonRpcResponse::error(
+                            id,
+                            INVALID_PARAMS,
+                            "x and y required".to_string(),
+                        )
+                    }
+                };
+                self.window_action(id, window_label, |window| {
+                    window.set_position(tauri::LogicalPosition::new(x, y))
+                })
+                .await
+            }
+
+            "minimize_window" => {
+                self.window_action(id, window_label, |window| window.minimize())
+                    .await
+            }
+
+            "maximize_window" => {
+                self.window_action(id, window_label, |window| window.maximize())
+                    .await
+            }
+
+            "unmaximize_window" => {
+                self.window_action(id, window_label, |window| window.unmaximize())
+                    .await
+            }
+
+            "set_fullscreen" => {
+                let fullscreen = request
+                    .params
+                    .get("fullscreen")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true);
+                self.window_action(id, window_label, |window| window.set_fullscreen(fullscreen))
+                    .await
+            }
+
+            "show_window" => {
+                self.window_action(id, window_label, |window| window.show())
+                    .await
+            }
+
+            "hide_window" => {
+                self.window_action(id, window_label, |window| window.hide())
+                    .await
+            }
+
+            "wait_for_window" => {
+                let label = request.params.get("label").and_then(|v| v.as_str());
+                let title = request.params.get("title").and_then(|v| v.as_str());
+                let url = request.params.get("url").and_then(|v| v.as_str());
+                // Only windows created from now on, unless an earlier cursor is given
+                // (0 includes every window created since launch)
+                let since = request
+                    .params
+                    .get("since")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or_else(|| self.state.window_events.cursor());
+                let timeout_ms = request
+                    .params
+                    .get("timeout_ms")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(10_000);
+
+                let start = tokio::time::Instant::now();
+                let deadline = start + tokio::time::Duration::from_millis(timeout_ms);
+                loop {
+                    // Register for the next creation before checking to avoid missing it
+                    let created = self.state.window_created.notified();
+                    if let Some(window) = self.find_window(label, title, url, since) {
+                        let mut state = Self::window_state(&window);
+                        let webviews: Vec<serde_json::Value> =
+                            window.webviews().iter().map(Self::webview_info).collect();
+                        state["webviews"] = serde_json::json!(webviews);
+                        state["elapsed_ms"] = serde_json::json!(start.elapsed().as_millis() as u64);
+                        return JsonRpcResponse::success(id, state);
+                    }
+
+                    let now = tokio::time::Instant::now();
+                    if now >= deadline {
+                        return JsonRpcResponse::error(
+                            id,
+                            EVAL_ERROR,
+                            format!(
+                                "Timeout after {}ms waiting for window (label: {:?}, title: {:?}, url: {:?})",
+                                timeout_ms, label, title, url
+                            ),
+                        );
+                    }
+                    // Wake on window creation, or poll since titles and URLs change after creation
+                    let wait = (deadline - now).min(tokio::time::Duration::from_millis(250));
+                    let _ = tokio::time::timeout(wait, created).await;
+                }
+            }
+
+            "intercept_popups" => {
+                let mode = request
+                    .params
+                    .get("mode")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("report");
+                let js = commands::intercept_popups_js(mode);
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "get_popups" => {
+                let clear = request
+                    .params
+                    .get("clear")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let js = commands::get_popups_js(clear);
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "get_window_events" => {
+                let since = request
+                    .params
+                    .get("since")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                let clear = request
+                    .params
+                    .get("clear")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let (events, cursor, dropped) = self.state.window_events.since(since, window_label);
+                if clear {
+                    self.state.window_events.clear();
                 }
+                JsonRpcResponse::success(
+                    id,
+                    serde_json::json!({
+                        "events": events,
+                        "cursor": cursor,
+                        "dropped": dropped
+                    }),
+                )
             }
 
             "snapshot" => {
+                let options = match commands::SnapshotOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let js = commands::snapshot_js(&options);
                 match self
-                    .eval_with_result_on_window(window_label, commands::SNAPSHOT_JS)
+                    .eval_with_result_on_window(window_label, webview_label, &js)
                     .await
                 {
                     Ok(result) => JsonRpcResponse::success(id, result),
@@ -289,53 +1509,287 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                 }
             }
 
-            "click" => {
-                let js = if let Some(ref_num) = request.params.get("ref").and_then(|v| v.as_u64()) {
-                    commands::click_ref_js(ref_num as u32)
+            "click" | "fill" => {
+                let options = match commands::ActionOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let ref_num = request
+                    .params
+                    .get("ref")
+                    .and_then(|v| v.as_u64())
+                    .map(|r| r as u32);
+                let selector = request.params.get("selector").and_then(|v| v.as_str());
+                if ref_num.is_none() && selector.is_none() {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        "Either ref or selector must be provided".to_string(),
+                    );
+                }
+                let js = if request.method == "click" {
+                    commands::click_js(ref_num, selector, &options)
                 } else {
-                    let selector = request
+                    let value = request
                         .params
-                        .get("selector")
+                        .get("value")
                         .and_then(|v| v.as_str())
                         .unwrap_or("");
-                    commands::click_js(selector)
+                    commands::fill_js(ref_num, selector, value, &options)
                 };
-                match self.eval_with_result_on_window(window_label, &js).await {
+                // The in-page checks may retry for the whole action timeout
+                let timeout = tokio::time::Duration::from_millis(options.timeout_ms)
+                    + tokio::time::Duration::from_secs(5);
+                match self
+                    .eval_with_timeout(window_label, webview_label, &js, timeout)
+                    .await
+                {
                     Ok(result) => JsonRpcResponse::success(id, result),
                     Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
             }
 
-            "fill" => {
-                let value = request
+            "hover" | "dblclick" | "right_click" => {
+                let options = match commands::ActionOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let ref_num = request
                     .params
-                    .get("value")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-                let js = if let Some(ref_num) = request.params.get("ref").and_then(|v| v.as_u64()) {
-                    commands::fill_ref_js(ref_num as u32, value)
-                } else {
-                    let selector = request
-                        .params
-                        .get("selector")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("");
-                    commands::fill_js(selector, value)
+                    .get("ref")
+                    .and_then(|v| v.as_u64())
+                    .map(|r| r as u32);
+                let selector = request.params.get("selector").and_then(|v| v.as_str());
+                if ref_num.is_none() && selector.is_none() {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        "Either ref or selector must be provided".to_string(),
+                    );
+                }
+                let js = commands::pointer_action_js(&request.method, ref_num, selector, &options);
+                let timeout = tokio::time::Duration::from_millis(options.timeout_ms)
+                    + tokio::time::Duration::from_secs(5);
+                match self
+                    .eval_with_timeout(window_label, webview_label, &js, timeout)
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "mouse_down" | "mouse_up" | "mouse_move" => {
+                let (x, y) = match self
+                    .image_point(window_label, webview_label, &request.params)
+                    .await
+                {
+                    Ok(point) => point,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let button = match request.params.get("button") {
+                    Some(button) => match serde_json::from_value(button.clone()) {
+                        Ok(button) => button,
+                        Err(e) => {
+                            return JsonRpcResponse::error(
+                                id,
+                                INVALID_PARAMS,
+                                format!("Invalid button: {}", e),
+                            )
+                        }
+                    },
+                    None => commands::MouseButton::default(),
+                };
+                let action = request.method.trim_start_matches("mouse_");
+                let js = commands::mouse_js(action, x, y, button);
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "drag" => {
+                let options = match commands::ActionOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
                 };
-                match self.eval_with_result_on_window(window_label, &js).await {
+                let params = &request.params;
+                let ref_param =
+                    |key: &str| params.get(key).and_then(|v| v.as_u64()).map(|r| r as u32);
+                let from = (
+                    ref_param("from_ref"),
+                    params.get("from_selector").and_then(|v| v.as_str()),
+                );
+                let to = (
+                    ref_param("to_ref"),
+                    params.get("to_selector").and_then(|v| v.as_str()),
+                );
+                if from == (None, None) || to == (None, None) {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        "Provide from_ref or from_selector, and to_ref or to_selector".to_string(),
+                    );
+                }
+                let steps = request
+                    .params
+                    .get("steps")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(10)
+                    .clamp(1, MAX_DRAG_STEPS) as u32;
+                let js = commands::drag_js(from, to, steps, &options);
+                // Two actionability waits plus one animation frame (~16ms) per step
+                let timeout =
+                    tokio::time::Duration::from_millis(options.timeout_ms.saturating_mul(2))
+                        + tokio::time::Duration::from_millis(16 * steps as u64)
+                        + tokio::time::Duration::from_secs(10);
+                match self
+                    .eval_with_timeout(window_label, webview_label, &js, timeout)
+                    .await
+                {
                     Ok(result) => JsonRpcResponse::success(id, result),
                     Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
             }
 
-            "press_key" => {
-                let key = request
+            "select_option" | "set_checked" | "set_input_files" => {
+                let options = match commands::ActionOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let ref_num = request
                     .params
-                    .get("key")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-                let js = commands::press_key_js(key);
-                match self.eval_with_result_on_window(window_label, &js).await {
+                    .get("ref")
+                    .and_then(|v| v.as_u64())
+                    .map(|r| r as u32);
+                let selector = request.params.get("selector").and_then(|v| v.as_str());
+                if ref_num.is_none() && selector.is_none() {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        "Either ref or selector must be provided".to_string(),
+                    );
+                }
+                let js = match request.method.as_str() {
+                    "select_option" => {
+                        let values = match request.params.get("values") {
+                            Some(serde_json::Value::Array(values)) => {
+                                serde_json::Value::Array(values.clone())
+                            }
+                            Some(value) => serde_json::Value::Array(vec![value.clone()]),
+                            None => {
+                                return JsonRpcResponse::error(
+                                    id,
+                                    INVALID_PARAMS,
+                                    "values is required".to_string(),
+                                )
+                            }
+                        };
+                        commands::select_option_js(ref_num, selector, &values, &options)
+                    }
+                    "set_checked" => {
+                        let checked = request
+                            .params
+                            .get("checked")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(true);
+                        commands::set_checked_js(ref_num, selector, checked, &options)
+                    }
+                    _ => {
+                        let paths: Vec<String> = match request.params.get("paths") {
+                            Some(paths) => match serde_json::from_value(paths.clone()) {
+                                Ok(paths) => paths,
+                                Err(e) => {
+                                    return JsonRpcResponse::error(
+                                        id,
+                                        INVALID_PARAMS,
+                                        format!("paths must be an array of file paths: {}", e),
+                                    )
+                                }
+                            },
+                            None => Vec::new(),
+                        };
+                        let files = match tokio::task::spawn_blocking(move || {
+                            commands::read_input_files(&get_project_root(), &paths)
+                        })
+                        .await
+                        {
+                            Ok(Ok(files)) => files,
+                            Ok(Err(e)) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                            Err(e) => {
+                                return JsonRpcResponse::error(
+                                    id,
+                                    EVAL_ERROR,
+                                    format!("Failed to read files: {}", e),
+                                )
+                            }
+                        };
+                        commands::set_input_files_js(ref_num, selector, &files, &options)
+                    }
+                };
+                let timeout = tokio::time::Duration::from_millis(options.timeout_ms)
+                    + tokio::time::Duration::from_secs(5);
+                match self
+                    .eval_with_timeout(window_label, webview_label, &js, timeout)
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "press" | "press_key" | "type_text" => {
+                let options = match commands::ActionOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let ref_num = request
+                    .params
+                    .get("ref")
+                    .and_then(|v| v.as_u64())
+                    .map(|r| r as u32);
+                let selector = request.params.get("selector").and_then(|v| v.as_str());
+                let mut timeout = tokio::time::Duration::from_millis(options.timeout_ms)
+                    + tokio::time::Duration::from_secs(5);
+                let js = if request.method == "type_text" {
+                    let Some(text) = request.params.get("text").and_then(|v| v.as_str()) else {
+                        return JsonRpcResponse::error(
+                            id,
+                            INVALID_PARAMS,
+                            "text is required".to_string(),
+                        );
+                    };
+                    let delay_ms = request
+                        .params
+                        .get("delay_ms")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0);
+                    timeout += tokio::time::Duration::from_millis(
+                        delay_ms.saturating_mul(text.chars().count() as u64),
+                    );
+                    commands::type_text_js(ref_num, selector, text, delay_ms, &options)
+                } else {
+                    let Some(key) = request
+                        .params
+                        .get("key")
+                        .and_then(|v| v.as_str())
+                        .filter(|key| !key.is_empty())
+                    else {
+                        return JsonRpcResponse::error(
+                            id,
+                            INVALID_PARAMS,
+                            "key is required".to_string(),
+                        );
+                    };
+                    commands::press_js(ref_num, selector, key, &options)
+                };
+                match self
+                    .eval_with_timeout(window_label, webview_label, &js, timeout)
+                    .await
+                {
                     Ok(result) => JsonRpcResponse::success(id, result),
                     Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
@@ -349,7 +1803,7 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                     .unwrap_or("");
                 let wrapped = format!("return ({});", script);
                 match self
-                    .eval_with_result_on_window(window_label, &wrapped)
+                    .eval_with_result_on_window(window_label, webview_label, &wrapped)
                     .await
                 {
                     Ok(result) => JsonRpcResponse::success(id, result),
@@ -364,7 +1818,10 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
                 let js = commands::navigate_js(url);
-                match self.eval_with_result_on_window(window_label, &js).await {
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
                     Ok(result) => JsonRpcResponse::success(id, result),
                     Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
@@ -372,9 +1829,15 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
 
             "get_window_id" => {
                 // Get the macOS CGWindowID for use with screencapture command
+                let window = match self.target_window(window_label, webview_label) {
+                    Ok(window) => window,
+                    Err(e) => return JsonRpcResponse::error(id, EVAL_ERROR, e),
+                };
+                let label = window.label().to_string();
+                let matcher = Self::window_matcher(&window);
                 let pid = std::process::id();
                 let result = tokio::task::spawn_blocking(move || {
-                    commands::screenshot::get_window_id_by_pid(pid)
+                    commands::screenshot::get_window_id_by_pid(pid, Some(&matcher))
                 })
                 .await;
 
@@ -383,7 +1846,8 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                         id,
                         serde_json::json!({
                             "window_id": window_id,
-                            "pid": pid
+                            "pid": pid,
+                            "window": label
                         }),
                     ),
                     Ok(Err(e)) => JsonRpcResponse::error(id, EVAL_ERROR, e),
@@ -393,53 +1857,390 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                 }
             }
 
-            "screenshot" => {
-                // Try native screenshot first with timeout, fallback to JS-based html2canvas
-                // Use spawn_blocking to avoid blocking the async runtime
-                let pid = std::process::id();
-                let native_task = tokio::task::spawn_blocking(move || {
-                    commands::screenshot::capture_window_by_pid(pid)
-                });
+            "screenshot_transform" => {
+                // Transform for a window capture taken outside the plugin (macOS screencapture)
+                let (Some(width), Some(height)) = (
+                    request.params.get("width").and_then(|v| v.as_u64()),
+                    request.params.get("height").and_then(|v| v.as_u64()),
+                ) else {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        "width and height are required".to_string(),
+                    );
+                };
+                let masks = match self
+                    .capture_masks(window_label, webview_label, &CaptureSetup::default())
+                    .await
+                {
+                    Ok(masks) => masks,
+                    Err(e) => return JsonRpcResponse::error(id, EVAL_ERROR, e),
+                };
+                let transform = commands::screenshot::ImageMapping::new(
+                    &masks.geometry,
+                    masks.device_pixel_ratio,
+                    width as u32,
+                    height as u32,
+                )
+                .transform();
+                self.remember_transform(window_label, webview_label, transform)
+                    .await;
+                JsonRpcResponse::success(id, serde_json::json!(transform))
+            }
 
-                // Give native screenshot 5 seconds, then fall back to JS
-                let native_result =
-                    tokio::time::timeout(tokio::time::Duration::from_secs(5), native_task).await;
-
-                match native_result {
-                    Ok(Ok(Ok(result))) => JsonRpcResponse::success(id, result),
-                    Ok(Ok(Err(e))) => {
-                        tracing::warn!("Native screenshot failed: {}, falling back to JS", e);
-                        let screenshot_js = commands::SCREENSHOT_JS;
-                        match self
-                            .eval_with_result_on_window(window_label, screenshot_js)
-                            .await
-                        {
-                            Ok(result) => JsonRpcResponse::success(id, result),
-                            Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
-                        }
+            "click_at" | "element_at" => {
+                let (x, y) = match self
+                    .image_point(window_label, webview_label, &request.params)
+                    .await
+                {
+                    Ok(point) => point,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let js = if request.method == "click_at" {
+                    commands::click_at_js(x, y)
+                } else {
+                    commands::element_at_js(x, y)
+                };
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "locate" => {
+                let selector = match request.params.get("selector").and_then(|v| v.as_str()) {
+                    Some(selector) if !selector.trim().is_empty() => selector,
+                    _ => {
+                        return JsonRpcResponse::error(
+                            id,
+                            INVALID_PARAMS,
+                            "selector must be provided".to_string(),
+                        )
                     }
-                    Ok(Err(e)) => {
-                        tracing::warn!("Screenshot task panicked: {}, falling back to JS", e);
-                        let screenshot_js = commands::SCREENSHOT_JS;
-                        match self
-                            .eval_with_result_on_window(window_label, screenshot_js)
-                            .await
-                        {
-                            Ok(result) => JsonRpcResponse::success(id, result),
-                            Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                };
+                let limit = request
+                    .params
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(20) as usize;
+                let js = commands::locate_js(selector, limit);
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "screenshot" => {
+                let image_options =
+                    match ImageOptions::from_params(&request.params, &get_project_root()) {
+                        Ok(options) => options,
+                        Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                    };
+                let setup = match CaptureSetup::from_params(&request.params) {
+                    Ok(setup) => setup,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let flag = |name: &str| {
+                    request
+                        .params
+                        .get(name)
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false)
+                };
+                if flag("annotate") && flag("full_page") {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        "annotate is not supported with full_page".to_string(),
+                    );
+                }
+                let result = self
+                    .capture_screenshot(
+                        window_label,
+                        webview_label,
+                        &request.params,
+                        &image_options,
+                        &setup,
+                    )
+                    .await;
+                self.screenshot_response(id, window_label, webview_label, result, &image_options)
+                    .await
+            }
+
+            "screenshot_element" => {
+                let ref_num = request
+                    .params
+                    .get("ref")
+                    .and_then(|v| v.as_u64())
+                    .map(|r| r as u32);
+                let selector = request.params.get("selector").and_then(|v| v.as_str());
+                if ref_num.is_none() && selector.is_none() {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        "Either ref or selector must be provided".to_string(),
+                    );
+                }
+                let image_options =
+                    match ImageOptions::from_params(&request.params, &get_project_root()) {
+                        Ok(options) => options,
+                        Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                    };
+                let setup = match CaptureSetup::from_params(&request.params) {
+                    Ok(setup) => setup,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+
+                self.prepare_capture(window_label, webview_label, &setup)
+                    .await;
+                let result = self
+                    .element_screenshot(
+                        window_label,
+                        webview_label,
+                        ref_num,
+                        selector,
+                        &image_options,
+                        &setup,
+                    )
+                    .await;
+                self.restore_capture(window_label, webview_label, &setup)
+                    .await;
+                self.screenshot_response(id, window_label, webview_label, result, &image_options)
+                    .await
+            }
+
+            "wait_for" => {
+                let condition = match commands::WaitCondition::from_params(&request.params) {
+                    Ok(condition) => condition,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                match self
+                    .wait_in_page(
+                        window_label,
+                        webview_label,
+                        condition.timeout_ms,
+                        &condition.describe(),
+                        |slice_ms| commands::wait_for_js(&condition, slice_ms),
+                    )
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "wait_for_idle" => {
+                let options = match commands::IdleOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                match self
+                    .wait_in_page(
+                        window_label,
+                        webview_label,
+                        options.timeout_ms,
+                        "network and IPC to be idle",
+                        |slice_ms| commands::wait_for_idle_js(&options, slice_ms),
+                    )
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "wait_for_visual_stable" => {
+                let options = match StabilityOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let ref_num = request
+                    .params
+                    .get("ref")
+                    .and_then(|v| v.as_u64())
+                    .map(|r| r as u32);
+                let selector = request.params.get("selector").and_then(|v| v.as_str());
+                match self
+                    .wait_for_visual_stable(
+                        window_label,
+                        webview_label,
+                        ref_num,
+                        selector,
+                        &options,
+                    )
+                    .await
+                {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "start_recording" => {
+                let options = match RecordingOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let window = match self.target_window(window_label, webview_label) {
+                    Ok(window) => window,
+                    Err(e) => return JsonRpcResponse::error(id, EVAL_ERROR, e),
+                };
+
+                let mut recording = self.state.recording.lock().await;
+                if let Some(active) = recording.as_ref() {
+                    return JsonRpcResponse::error(
+                        id,
+                        EVAL_ERROR,
+                        format!(
+                            "A recording of window '{}' is already in progress; call stop_recording first",
+                            active.window()
+                        ),
+                    );
+                }
+                let fps = options.fps;
+                *recording = Some(Recording::start(
+                    window.label().to_string(),
+                    std::process::id(),
+                    Self::window_matcher(&window),
+                    options,
+                ));
+                JsonRpcResponse::success(
+                    id,
+                    serde_json::json!({
+                        "recording": true,
+                        "window": window.label(),
+                        "fps": fps,
+                    }),
+                )
+            }
+
+            "stop_recording" => {
+                let format: RecordingFormat = match request.params.get("format") {
+                    Some(format) => match serde_json::from_value(format.clone()) {
+                        Ok(format) => format,
+                        Err(_) => {
+                            return JsonRpcResponse::error(
+                                id,
+                                INVALID_PARAMS,
+                                "format must be 'apng' or 'png'".to_string(),
+                            )
                         }
-                    }
-                    Err(_) => {
-                        tracing::warn!("Native screenshot timed out, falling back to JS");
-                        let screenshot_js = commands::SCREENSHOT_JS;
-                        match self
-                            .eval_with_result_on_window(window_label, screenshot_js)
-                            .await
-                        {
-                            Ok(result) => JsonRpcResponse::success(id, result),
-                            Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                    },
+                    None => RecordingFormat::default(),
+                };
+                let path = request
+                    .params
+                    .get("path")
+                    .and_then(|v| v.as_str())
+                    .map(|path| get_project_root().join(path))
+                    .unwrap_or_else(|| recording::default_path(format));
+
+                let Some(recording) = self.state.recording.lock().await.take() else {
+                    return JsonRpcResponse::error(
+                        id,
+                        EVAL_ERROR,
+                        "No recording in progress".to_string(),
+                    );
+                };
+                match recording.finish(format, path).await {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
+                }
+            }
+
+            "compare_screenshot" => {
+                let Some(name) = request.params.get("name").and_then(|v| v.as_str()) else {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        "name is required".to_string(),
+                    );
+                };
+                let update = request
+                    .params
+                    .get("update")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let compare_options = match CompareOptions::from_params(&request.params) {
+                    Ok(options) => options,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                let baseline_path = match compare::baseline_path(&get_project_root(), name) {
+                    Ok(path) => path,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+                // Compare lossless captures; the image is never written to a custom path
+                let mut image_options =
+                    match ImageOptions::from_params(&request.params, &get_project_root()) {
+                        Ok(options) => options,
+                        Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                    };
+                image_options.format = Some(OutputFormat::Png);
+                image_options.path = None;
+                let setup = match CaptureSetup::from_params(&request.params) {
+                    Ok(setup) => setup,
+                    Err(e) => return JsonRpcResponse::error(id, INVALID_PARAMS, e),
+                };
+
+                let screenshot = match self
+                    .capture_screenshot(
+                        window_label,
+                        webview_label,
+                        &request.params,
+                        &image_options,
+                        &setup,
+                    )
+                    .await
+                {
+                    Ok(screenshot) => screenshot,
+                    Err(e) => return JsonRpcResponse::error(id, EVAL_ERROR, e),
+                };
+                // In-page renders differ from native captures pixel by pixel, so
+                // they must never be compared against (or become) a baseline
+                if screenshot["rendered"].as_bool().unwrap_or(false) {
+                    return JsonRpcResponse::error(
+                        id,
+                        EVAL_ERROR,
+                        "Native capture failed and compare_screenshot does not use the in-page renderer; check that the window is visible and screen capture is permitted".to_string(),
+                    );
+                }
+                let compared = tokio::task::spawn_blocking(move || {
+                    let actual = compare::decode_screenshot(&screenshot)?;
+                    let baseline = compare::load_baseline(&baseline_path)?;
+                    let diff_path = compare::diff_path(&baseline_path);
+                    let mut result = match baseline {
+                        Some(baseline) if !update => compare::compare_images(
+                            &baseline,
+                            &actual,
+                            &compare_options,
+                            &diff_path,
+                        )?,
+                        existing => {
+                            // Missing baselines are created, like snapshot tests do
+                            compare::save_baseline(&baseline_path, &actual)?;
+                            serde_json::json!({
+                                "passed": true,
+                                "created": existing.is_none(),
+                                "updated": existing.is_some(),
+                                "width": actual.width(),
+                                "height": actual.height(),
+                            })
                         }
-                    }
+                    };
+                    result["baseline"] = serde_json::json!(baseline_path.display().to_string());
+                    Ok::<_, String>(result)
+                })
+                .await
+                .unwrap_or_else(|e| Err(format!("Task panicked: {}", e)));
+
+                match compared {
+                    Ok(result) => JsonRpcResponse::success(id, result),
+                    Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
             }
 
@@ -450,7 +2251,10 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
                 let js = commands::get_console_logs_js(clear);
-                match self.eval_with_result_on_window(window_label, &js).await {
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
                     Ok(result) => JsonRpcResponse::success(id, result),
                     Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
@@ -463,7 +2267,10 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
                 let js = commands::get_network_logs_js(clear);
-                match self.eval_with_result_on_window(window_label, &js).await {
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
                     Ok(result) => JsonRpcResponse::success(id, result),
                     Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
@@ -476,7 +2283,10 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
                 let js = commands::get_frontend_logs_js(clear);
-                match self.eval_with_result_on_window(window_label, &js).await {
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
                     Ok(result) => JsonRpcResponse::success(id, result),
                     Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
@@ -489,7 +2299,10 @@ impl<R: Runtime + 'static> CommandHandler for IpcCommandHandler<R> {
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
                 let js = commands::get_hmr_updates_js(clear);
-                match self.eval_with_result_on_window(window_label, &js).await {
+                match self
+                    .eval_with_result_on_window(window_label, webview_label, &js)
+                    .await
+                {
                     Ok(result) => JsonRpcResponse::success(id, result),
                     Err(e) => JsonRpcResponse::error(id, EVAL_ERROR, e),
                 }
@@ -513,16 +2326,20 @@ fn should_open_devtools() -> bool {
 
 /// Register the JS bridge - called from frontend
 /// This is called when initMcpBridge() is invoked in the frontend
+/// Returns the page scripts ([`commands::PAGE_SCRIPTS_JS`]) for the bridge to install
 #[tauri::command]
 async fn register_bridge<R: Runtime>(
     webview: Webview<R>,
     app: AppHandle<R>,
     state: State<'_, Arc<McpState>>,
-) -> Result<(), String> {
+) -> Result<String, String> {
     let label = webview.label().to_string();
-    eprintln!("[tauri-plugin-mcp] JS bridge registered for window: {}", label);
-    info!("JS bridge registered for window: {}", label);
-    state.set_window_initialized(label).await;
+    eprintln!(
+        "[tauri-plugin-mcp] JS bridge registered for webview: {}",
+        label
+    );
+    info!("JS bridge registered for webview: {}", label);
+    state.set_webview_initialized(label).await;
 
     // Open devtools if requested via environment variable
     if should_open_devtools() {
@@ -532,7 +2349,7 @@ async fn register_bridge<R: Runtime>(
         }
     }
 
-    Ok(())
+    Ok(commands::PAGE_SCRIPTS_JS.to_string())
 }
 
 /// Receive eval result from JS bridge
@@ -583,6 +2400,21 @@ fn get_project_root() -> std::path::PathBuf {
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("mcp")
         .invoke_handler(tauri::generate_handler![register_bridge, eval_result])
+        .on_window_ready(|window| {
+            if let Some(state) = window.try_state::<Arc<McpState>>() {
+                state.window_events.record(window.label(), "created", None);
+                state.window_created.notify_waiters();
+            }
+        })
+        .on_event(|app, event| {
+            if let RunEvent::WindowEvent { label, event, .. } = event {
+                if let Some((name, data)) = window_events::describe(event) {
+                    if let Some(state) = app.try_state::<Arc<McpState>>() {
+                        state.window_events.record(label, name, data);
+                    }
+                }
+            }
+        })
         .setup(|app, _api| {
             let project_root = get_project_root();
             eprintln!(
@@ -0,0 +1,320 @@
+//! Window screen recording from repeated native captures
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ExtendedColorType, ImageEncoder, ImageFormat};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::commands::screenshot::{self, WindowMatcher};
+
+/// Memory budget for the encoded frames of one recording
+const MAX_RECORDING_BYTES: usize = 256 * 1024 * 1024;
+
+/// Options for a new recording
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default)]
+pub struct RecordingOptions {
+    /// Frames per second, 1-30
+    pub fps: u32,
+    /// Capturing stops after this long, even if the recording is not stopped
+    pub max_duration_ms: u64,
+    /// Frames are downscaled to fit these dimensions
+    pub max_width: u32,
+    pub max_height: u32,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            fps: 5,
+            max_duration_ms: 60_000,
+            max_width: 1280,
+            max_height: 720,
+        }
+    }
+}
+
+impl RecordingOptions {
+    /// Read recording options from request params
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        let options: RecordingOptions = if params.is_object() {
+            serde_json::from_value(params.clone())
+                .map_err(|e| format!("Invalid recording options: {}", e))?
+        } else {
+            RecordingOptions::default()
+        };
+        if !(1..=30).contains(&options.fps) {
+            return Err("fps must be between 1 and 30".to_string());
+        }
+        if options.max_width == 0 || options.max_height == 0 {
+            return Err("max_width and max_height must be positive".to_string());
+        }
+        Ok(options)
+    }
+}
+
+/// Output written when a recording stops
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingFormat {
+    /// Animated PNG
+    #[default]
+    Apng,
+    /// Numbered PNG files in a directory
+    Png,
+}
+
+/// A PNG-encoded frame and when it was captured
+struct Frame {
+    offset_ms: u64,
+    png: Vec<u8>,
+}
+
+/// Frames collected by the capture task
+#[derive(Default)]
+struct Captured {
+    frames: Vec<Frame>,
+    width: u32,
+    height: u32,
+    /// Capturing stopped early because of the duration or memory limit
+    truncated: bool,
+    failed: usize,
+    last_error: Option<String>,
+}
+
+/// A recording in progress
+///
+/// Frames are kept PNG-compressed in memory so long recordings stay bounded.
+pub struct Recording {
+    window: String,
+    fps: u32,
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<Captured>,
+}
+
+impl Recording {
+    /// Start capturing the window described by `matcher` on a background task
+    pub fn start(
+        window: String,
+        pid: u32,
+        matcher: WindowMatcher,
+        options: RecordingOptions,
+    ) -> Self {
+        let (stop, stop_rx) = oneshot::channel();
+        let fps = options.fps;
+        let task = tokio::spawn(capture_frames(pid, matcher, options, stop_rx));
+        Self {
+            window,
+            fps,
+            stop,
+            task,
+        }
+    }
+
+    /// Label of the recorded window
+    pub fn window(&self) -> &str {
+        &self.window
+    }
+
+    /// Stop capturing and write the recording to `path`
+    pub async fn finish(
+        self,
+        format: RecordingFormat,
+        path: PathBuf,
+    ) -> Result<serde_json::Value, String> {
+        // The task may already have ended at a limit, so a failed send is fine
+        let _ = self.stop.send(());
+        let captured = self
+            .task
+            .await
+            .map_err(|e| format!("Recording task failed: {}", e))?;
+        if captured.frames.is_empty() {
+            return Err(match captured.last_error {
+                Some(e) => format!("No frames captured: {}", e),
+                None => "No frames captured".to_string(),
+            });
+        }
+
+        let fps = self.fps;
+        let window = self.window;
+        tokio::task::spawn_blocking(move || {
+            let frame_ms = 1000 / fps as u64;
+            let duration_ms = captured.frames.last().map_or(0, |f| f.offset_ms) + frame_ms;
+            match format {
+                RecordingFormat::Apng => write_apng(&captured, &path, frame_ms)?,
+                RecordingFormat::Png => write_frames(&captured, &path)?,
+            }
+            Ok(serde_json::json!({
+                "path": path.display().to_string(),
+                "format": match format {
+                    RecordingFormat::Apng => "apng",
+                    RecordingFormat::Png => "png",
+                },
+                "window": window,
+                "frames": captured.frames.len(),
+                "duration_ms": duration_ms,
+                "fps": fps,
+                "width": captured.width,
+                "height": captured.height,
+                "truncated": captured.truncated,
+                "failed_frames": captured.failed,
+            }))
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Task panicked: {}", e)))
+    }
+}
+
+/// Capture frames at the requested rate until stopped or a limit is reached
+async fn capture_frames(
+    pid: u32,
+    matcher: WindowMatcher,
+    options: RecordingOptions,
+    mut stop: oneshot::Receiver<()>,
+) -> Captured {
+    let mut captured = Captured::default();
+    let mut bytes = 0;
+    let frame_interval = Duration::from_millis(1000 / options.fps as u64);
+    let started = Instant::now();
+    let mut next_frame = tokio::time::Instant::now();
+
+    loop {
+        // Wait until the next frame is due, unless stopped first
+        if tokio::time::timeout_at(next_frame, &mut stop).await.is_ok() {
+            break;
+        }
+        // Slow captures lower the frame rate instead of bursting to catch up
+        next_frame = (next_frame + frame_interval).max(tokio::time::Instant::now());
+
+        let offset_ms = started.elapsed().as_millis() as u64;
+        if offset_ms > options.max_duration_ms {
+            captured.truncated = true;
+            break;
+        }
+
+        // Every frame must match the size of the first one
+        let size = (!captured.frames.is_empty()).then_some((captured.width, captured.height));
+        let matcher = matcher.clone();
+        let (max_width, max_height) = (options.max_width, options.max_height);
+        let result = tokio::task::spawn_blocking(move || {
+            capture_frame(pid, &matcher, size, max_width, max_height)
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Task panicked: {}", e)));
+
+        match result {
+            Ok((png, width, height)) => {
+                captured.width = width;
+                captured.height = height;
+                bytes += png.len();
+                captured.frames.push(Frame { offset_ms, png });
+                if bytes > MAX_RECORDING_BYTES {
+                    captured.truncated = true;
+                    break;
+                }
+            }
+            Err(e) => {
+                captured.failed += 1;
+                captured.last_error = Some(e);
+            }
+        }
+    }
+    captured
+}
+
+/// Capture one frame, resized to `size` or to fit the maximum dimensions
+fn capture_frame(
+    pid: u32,
+    matcher: &WindowMatcher,
+    size: Option<(u32, u32)>,
+    max_width: u32,
+    max_height: u32,
+) -> Result<(Vec<u8>, u32, u32), String> {
+    let image = screenshot::capture_image_by_pid(pid, Some(matcher))?;
+    let (width, height) = size.unwrap_or_else(|| {
+        screenshot::resize_dimensions(image.width(), image.height(), max_width, max_height)
+    });
+    let image = if (width, height) != image.dimensions() {
+        image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle)
+    } else {
+        image
+    };
+
+    // Fast compression keeps up with the frame rate
+    let mut png = Vec::new();
+    PngEncoder::new_with_quality(&mut png, CompressionType::Fast, FilterType::Adaptive)
+        .write_image(image.as_raw(), width, height, ExtendedColorType::Rgba8)
+        .map_err(|e| format!("Failed to encode frame: {}", e))?;
+    Ok((png, width, height))
+}
+
+/// Write the frames as an animated PNG, timed by their capture offsets
+fn write_apng(captured: &Captured, path: &Path, frame_ms: u64) -> Result<(), String> {
+    create_parent_dir(path)?;
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let apng_error = |e: png::EncodingError| format!("Failed to write {}: {}", path.display(), e);
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), captured.width, captured.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(captured.frames.len() as u32, 0)
+        .map_err(apng_error)?;
+    let mut writer = encoder.write_header().map_err(apng_error)?;
+
+    for (i, frame) in captured.frames.iter().enumerate() {
+        let delay_ms = captured
+            .frames
+            .get(i + 1)
+            .map_or(frame_ms, |next| next.offset_ms - frame.offset_ms);
+        writer
+            .set_frame_delay(delay_ms.clamp(1, u16::MAX as u64) as u16, 1000)
+            .map_err(apng_error)?;
+        let image = image::load_from_memory_with_format(&frame.png, ImageFormat::Png)
+            .map_err(|e| format!("Failed to decode frame: {}", e))?
+            .to_rgba8();
+        writer
+            .write_image_data(image.as_raw())
+            .map_err(apng_error)?;
+    }
+    writer.finish().map_err(apng_error)
+}
+
+/// Write the frames as numbered PNG files into the directory at `path`
+fn write_frames(captured: &Captured, path: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    for (i, frame) in captured.frames.iter().enumerate() {
+        let frame_path = path.join(format!("frame-{:05}.png", i + 1));
+        std::fs::write(&frame_path, &frame.png)
+            .map_err(|e| format!("Failed to write {}: {}", frame_path.display(), e))?;
+    }
+    Ok(())
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Default output path in the temp directory
+pub fn default_path(format: RecordingFormat) -> PathBuf {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let name = match format {
+        RecordingFormat::Apng => format!("tauri-mcp-recording-{}.png", timestamp),
+        RecordingFormat::Png => format!("tauri-mcp-recording-{}", timestamp),
+    };
+    std::env::temp_dir().join(name)
+}
@@ -0,0 +1,135 @@
+//! Window lifecycle event recording
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::WindowEvent;
+
+/// Maximum number of window events kept in memory
+const MAX_WINDOW_EVENTS: usize = 1000;
+
+/// A recorded window lifecycle event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WindowEventRecord {
+    /// Monotonic cursor, usable as `since` in later queries
+    pub id: u64,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    /// Label of the window the event belongs to
+    pub window: String,
+    /// Event name (e.g. "created", "resized", "close_requested")
+    pub event: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    events: VecDeque<WindowEventRecord>,
+}
+
+/// Bounded buffer of window events
+///
+/// Uses a std mutex because events are recorded from synchronous Tauri
+/// callbacks on the main thread.
+#[derive(Default)]
+pub struct WindowEventLog {
+    inner: Mutex<Inner>,
+}
+
+impl WindowEventLog {
+    /// Append an event, dropping the oldest one when the buffer is full
+    pub fn record(&self, window: &str, event: &str, data: Option<serde_json::Value>) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.next_id += 1;
+        let record = WindowEventRecord {
+            id: inner.next_id,
+            timestamp,
+            window: window.to_string(),
+            event: event.to_string(),
+            data,
+        };
+        inner.events.push_back(record);
+        if inner.events.len() > MAX_WINDOW_EVENTS {
+            inner.events.pop_front();
+        }
+    }
+
+    /// Cursor of the latest recorded event
+    pub fn cursor(&self) -> u64 {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).next_id
+    }
+
+    /// Get events recorded after the `since` cursor, optionally for one window
+    ///
+    /// Returns the matching events, the latest cursor, and whether events
+    /// after `since` were dropped from the buffer.
+    pub fn since(&self, since: u64, window: Option<&str>) -> (Vec<WindowEventRecord>, u64, bool) {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let events = inner
+            .events
+            .iter()
+            .filter(|e| e.id > since)
+            .filter(|e| window.is_none_or(|label| e.window == label))
+            .cloned()
+            .collect();
+        let dropped = inner
+            .events
+            .front()
+            .is_some_and(|oldest| oldest.id > since.saturating_add(1));
+        (events, inner.next_id, dropped)
+    }
+
+    /// Remove all recorded events (the cursor keeps increasing)
+    pub fn clear(&self) {
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .events
+            .clear();
+    }
+}
+
+/// Map a Tauri window event to a name and payload
+///
+/// Returns `None` for events that are not recorded (e.g. drag and drop).
+pub fn describe(event: &WindowEvent) -> Option<(&'static str, Option<serde_json::Value>)> {
+    match event {
+        WindowEvent::Resized(size) => Some((
+            "resized",
+            Some(serde_json::json!({ "width": size.width, "height": size.height })),
+        )),
+        WindowEvent::Moved(position) => Some((
+            "moved",
+            Some(serde_json::json!({ "x": position.x, "y": position.y })),
+        )),
+        WindowEvent::CloseRequested { .. } => Some(("close_requested", None)),
+        WindowEvent::Destroyed => Some(("destroyed", None)),
+        WindowEvent::Focused(true) => Some(("focused", None)),
+        WindowEvent::Focused(false) => Some(("blurred", None)),
+        WindowEvent::ScaleFactorChanged {
+            scale_factor,
+            new_inner_size,
+            ..
+        } => Some((
+            "scale_factor_changed",
+            Some(serde_json::json!({
+                "scale_factor": scale_factor,
+                "width": new_inner_size.width,
+                "height": new_inner_size.height
+            })),
+        )),
+        WindowEvent::ThemeChanged(theme) => Some((
+            "theme_changed",
+            Some(serde_json::json!({ "theme": theme.to_string() })),
+        )),
+        _ => None,
+    }
+}